@@ -0,0 +1,197 @@
+use super::board::GridState;
+use super::bot::heatmap;
+use super::engine::{ShotRecord, Side};
+use super::rules::Rules;
+use super::ship::Point;
+use super::view::BoardView;
+
+/// Shots at least this close to the best shot's chance of hitting are good
+const GOOD: f64 = 0.75;
+/// Shots further below the best shot than this are blunders
+const INACCURATE: f64 = 0.35;
+
+/// How a shot compares with the best one that could have been fired
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rating {
+    Good,
+    Inaccurate,
+    Blunder,
+}
+
+impl Rating {
+    /// Every rating, from best to worst
+    pub const ALL: [Rating; 3] = [Rating::Good, Rating::Inaccurate, Rating::Blunder];
+
+    /// Name of the rating
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Rating::Good => "Good",
+            Rating::Inaccurate => "Inaccurate",
+            Rating::Blunder => "Blunder",
+        }
+    }
+
+    /// Rates a shot by its score against the best shot
+    fn from_score(score: f64) -> Self {
+        if score >= GOOD {
+            Rating::Good
+        } else if score >= INACCURATE {
+            Rating::Inaccurate
+        } else {
+            Rating::Blunder
+        }
+    }
+}
+
+/// A shot that was fired, next to the best shot there was
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShotReview {
+    pub turn: u32,
+    pub target: Point,
+    /// The first cell, row by row, with the highest chance of hitting
+    pub best: Point,
+    /// The shot's chance of hitting as a share of the best shot's, from 0 to 1
+    pub score: f64,
+    pub rating: Rating,
+}
+
+/// Every shot one side fired in a game, rated
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub reviews: Vec<ShotReview>,
+}
+
+impl Analysis {
+    /// How well the shots were chosen on average, from 0 to 100
+    pub fn accuracy(&self) -> Option<f64> {
+        let total: f64 = self.reviews.iter().map(|review| review.score).sum();
+        (!self.reviews.is_empty()).then(|| total / self.reviews.len() as f64 * 100.0)
+    }
+
+    /// The number of shots given a rating
+    pub fn count(&self, rating: Rating) -> usize {
+        self.reviews
+            .iter()
+            .filter(|review| review.rating == rating)
+            .count()
+    }
+
+    /// Up to `count` of the shots that were not good, worst first
+    pub fn worst(&self, count: usize) -> Vec<&ShotReview> {
+        let mut worst: Vec<&ShotReview> = self
+            .reviews
+            .iter()
+            .filter(|review| review.rating != Rating::Good)
+            .collect();
+        worst.sort_by(|a, b| a.score.total_cmp(&b.score));
+        worst.truncate(count);
+        worst
+    }
+}
+
+/// Replays the shots `side` fired and rates each against the best shot given what was known
+/// then. The chance of a cell hitting is taken from how many placements of the ships not yet
+/// sunk could cover it, as the Admiral's hardest targeting sees it. The shots of a salvo are
+/// fired before any is revealed, so each is rated against the cells the salvo has left
+pub fn analyse(rules: &Rules, shots: &[ShotRecord], side: Side) -> Analysis {
+    let mut view = BoardView::new(rules.width, rules.height, rules.fleet.clone());
+    let fired: Vec<&ShotRecord> = shots.iter().filter(|shot| shot.shooter == side).collect();
+    let mut reviews = vec![];
+    for volley in fired.chunk_by(|a, b| a.turn == b.turn) {
+        let density = heatmap(&view);
+        for (index, shot) in volley.iter().enumerate() {
+            let taken: Vec<&Point> = volley[..index].iter().map(|shot| &shot.target).collect();
+            let Some(best) = best_cell(&view.board, &density, &taken) else {
+                continue;
+            };
+            let weight = |point: &Point| density[point.1 as usize][point.0 as usize] as f64;
+            let score = if weight(&best) > 0.0 {
+                weight(&shot.target) / weight(&best)
+            } else {
+                1.0
+            };
+            reviews.push(ShotReview {
+                turn: shot.turn,
+                target: shot.target.clone(),
+                best,
+                score,
+                rating: Rating::from_score(score),
+            });
+        }
+        for shot in volley {
+            view.record(&shot.target, &shot.outcome);
+        }
+        if rules.no_touching {
+            view.mark_around_sunk();
+        }
+    }
+    Analysis { reviews }
+}
+
+/// The first blank cell with the highest density, leaving out the cells already `taken`
+pub fn best_cell(
+    board: &[Vec<GridState>],
+    density: &[Vec<u32>],
+    taken: &[&Point],
+) -> Option<Point> {
+    let mut best: Option<(Point, u32)> = None;
+    for (y, row) in board.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let point = Point(x as u8, y as u8);
+            if *cell != GridState::Blank || taken.contains(&&point) {
+                continue;
+            }
+            if best
+                .as_ref()
+                .is_none_or(|(_, weight)| density[y][x] > *weight)
+            {
+                best = Some((point, density[y][x]));
+            }
+        }
+    }
+    best.map(|(point, _)| point)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::GameBoard;
+    use crate::engine::Game;
+    use crate::ship::{Ship, ShipDirection, ShipKind};
+
+    #[test]
+    fn shots_are_rated_against_the_best() {
+        let kind = ShipKind::build("Destroyer", 2).unwrap();
+        let rules = Rules::build(10, 10, vec![kind.clone()]).unwrap();
+        let board = |x, y| {
+            let ship = Ship::build(kind.clone(), x, y, ShipDirection::Left, &rules).unwrap();
+            GameBoard::build(vec![ship], &rules)
+        };
+        let mut game = Game::new(rules.clone(), board(0, 0), board(4, 4));
+        // A hit in the middle, a shot far from it, then the shot that sinks the ship
+        for (target, reply) in [(Point(4, 4), Point(9, 9)), (Point(9, 9), Point(8, 8))] {
+            game.fire(target).unwrap();
+            game.fire(reply).unwrap();
+        }
+        game.fire(Point(5, 4)).unwrap();
+
+        let analysis = analyse(&rules, game.history(), Side::Player);
+        let ratings: Vec<Rating> = analysis
+            .reviews
+            .iter()
+            .map(|review| review.rating)
+            .collect();
+        assert_eq!(ratings, [Rating::Good, Rating::Blunder, Rating::Good]);
+        assert_eq!(analysis.reviews[1].best, Point(4, 3));
+        assert_eq!(analysis.reviews[1].score, 0.0);
+        assert_eq!(analysis.count(Rating::Good), 2);
+        assert_eq!(analysis.accuracy().map(f64::round), Some(67.0));
+        assert_eq!(analysis.worst(5).len(), 1);
+        assert_eq!(analysis.worst(5)[0].target, Point(9, 9));
+
+        // The opponent's shots never came near the player's destroyer
+        let analysis = analyse(&rules, game.history(), Side::Opponent);
+        assert_eq!(analysis.reviews.len(), 2);
+        assert!(analysis.reviews[0].score < 1.0);
+    }
+}
@@ -0,0 +1,192 @@
+//! Pits two computer strategies against each other over many seeded games.
+//!
+//! cargo run --release --bin simulate -- --a density/hidden --b hunt/random --games 1000
+
+use cl_battleship::rules::{self, Rules};
+use cl_battleship::simulation::{SimulatedGame, Strategy, simulate_game};
+use rand::{SeedableRng, rngs::StdRng};
+
+const USAGE: &str = "Usage: simulate [--a <TARGETING>[/<PLACEMENT>]] [--b <TARGETING>[/<PLACEMENT>]] [--games <COUNT>] [--seed <NUMBER>] [--size <WIDTH>x<HEIGHT>] [--fleet <NAME>:<LENGTH>,...] [--salvo] [--no-touching]
+Targeting: random, hunt, parity, density
+Placement: aligned, random, spaced, hidden";
+
+/// Width of each bucket in the shots-to-win histogram
+const BUCKET: u32 = 10;
+
+/// Settings chosen on the command line
+struct Args {
+    strategies: [Strategy; 2],
+    games: u32,
+    seed: u64,
+    rules: Rules,
+}
+
+/// Reads the command line arguments
+fn parse_args() -> Result<Args, String> {
+    let defaults = Rules::default();
+    let (mut width, mut height) = (defaults.width, defaults.height);
+    let mut fleet = defaults.fleet;
+    let mut strategies = [
+        Strategy::parse("density/hidden")?,
+        Strategy::parse("hunt/random")?,
+    ];
+    let mut games = 1000;
+    let mut seed = 0;
+    let mut salvo = false;
+    let mut no_touching = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}.", arg));
+        match arg.as_str() {
+            "--a" => strategies[0] = Strategy::parse(&value()?)?,
+            "--b" => strategies[1] = Strategy::parse(&value()?)?,
+            "--games" => {
+                let count = value()?;
+                games = count
+                    .parse()
+                    .map_err(|_| format!("Invalid game count {}.", count))?;
+            }
+            "--seed" => {
+                let number = value()?;
+                seed = number
+                    .parse()
+                    .map_err(|_| format!("Invalid seed {}.", number))?;
+            }
+            "--size" => (width, height) = rules::parse_size(&value()?)?,
+            "--fleet" => fleet = rules::parse_fleet(&value()?)?,
+            "--salvo" => salvo = true,
+            "--no-touching" => no_touching = true,
+            _ => return Err(format!("Unknown argument {}.", arg)),
+        }
+    }
+    if games == 0 {
+        return Err("At least one game must be played.".to_string());
+    }
+    let mut rules = Rules::build(width, height, fleet)?;
+    rules.salvo = salvo;
+    rules.no_touching = no_touching;
+    if no_touching {
+        rules.check_no_touching()?;
+    }
+    Ok(Args {
+        strategies,
+        games,
+        seed,
+        rules,
+    })
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // Every game has its own seed so any one of them can be replayed,
+    // and the strategies take turns firing first
+    let results: Vec<SimulatedGame> = (0..args.games)
+        .map(|game| {
+            let mut rng = StdRng::seed_from_u64(args.seed.wrapping_add(game as u64));
+            let [a, b] = &args.strategies;
+            simulate_game(&args.rules, [a, b], game as usize % 2, &mut rng)
+        })
+        .collect();
+
+    report(&args, &results);
+}
+
+/// Prints win rates, shots to win and ship survival for both strategies
+fn report(args: &Args, results: &[SimulatedGame]) {
+    let games = results.len() as f64;
+    let names = args.strategies.map(|strategy| strategy.get_name());
+    println!(
+        "{} games on a {}x{} board, seeds {} to {}",
+        results.len(),
+        args.rules.width,
+        args.rules.height,
+        args.seed,
+        args.seed.wrapping_add(results.len() as u64 - 1)
+    );
+    println!();
+    println!(
+        "{:<22}{:<24}{:<24}",
+        "",
+        format!("A: {}", names[0]),
+        format!("B: {}", names[1])
+    );
+
+    let wins = [0, 1].map(|index| results.iter().filter(|game| game.winner == index).count());
+    println!(
+        "{:<22}{:<24}{:<24}",
+        "Wins",
+        format!("{} ({:.1}%)", wins[0], 100.0 * wins[0] as f64 / games),
+        format!("{} ({:.1}%)", wins[1], 100.0 * wins[1] as f64 / games)
+    );
+
+    let shots_to_win = [0, 1].map(|index| {
+        results
+            .iter()
+            .filter(|game| game.winner == index)
+            .map(|game| game.shots[index])
+            .collect::<Vec<u32>>()
+    });
+    let mean = shots_to_win.each_ref().map(|shots| {
+        if shots.is_empty() {
+            "-".to_string()
+        } else {
+            format!(
+                "{:.1}",
+                shots.iter().sum::<u32>() as f64 / shots.len() as f64
+            )
+        }
+    });
+    println!("{:<22}{:<24}{:<24}", "Mean shots to win", mean[0], mean[1]);
+
+    println!();
+    println!("Shots to win");
+    let all_shots = shots_to_win.iter().flatten();
+    if let (Some(min), Some(max)) = (all_shots.clone().min(), all_shots.max()) {
+        for bucket in (min / BUCKET)..=(max / BUCKET) {
+            let (low, high) = (bucket * BUCKET, bucket * BUCKET + BUCKET - 1);
+            let counts = shots_to_win.each_ref().map(|shots| {
+                shots
+                    .iter()
+                    .filter(|shots| (low..=high).contains(*shots))
+                    .count()
+            });
+            println!(
+                "{:<22}{:<24}{:<24}",
+                format!("  {}-{}", low, high),
+                bar(counts[0], results.len()),
+                bar(counts[1], results.len())
+            );
+        }
+    }
+
+    println!();
+    println!("Ship survival");
+    for (ship, kind) in args.rules.fleet.iter().enumerate() {
+        let survival = [0, 1].map(|index| {
+            let afloat = results
+                .iter()
+                .filter(|game| game.afloat[index][ship])
+                .count();
+            format!("{:.1}%", 100.0 * afloat as f64 / games)
+        });
+        println!(
+            "{:<22}{:<24}{:<24}",
+            format!("  {}", kind.get_name()),
+            survival[0],
+            survival[1]
+        );
+    }
+}
+
+/// A count with a bar scaled to the number of games
+fn bar(count: usize, games: usize) -> String {
+    let width = (count * 12).div_ceil(games.max(1));
+    format!("{:>5} {}", count, "#".repeat(width))
+}
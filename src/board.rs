@@ -0,0 +1,465 @@
+use super::bot::density_map;
+use super::rules::Rules;
+use super::ship::{Point, Ship, ShipDirection, ShipKind};
+use super::view::{BoardView, SunkReport};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// What is known about a cell of a board
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GridState {
+    Blank,
+    Hit,
+    Miss,
+}
+
+/// What happened when a shot was fired
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ShotOutcome {
+    Miss,
+    Hit { kind: ShipKind },
+    Sunk { kind: ShipKind },
+    /// The last ship of the fleet was sunk
+    FleetDestroyed { kind: ShipKind },
+}
+
+impl ShotOutcome {
+    /// Check weather the shot hit a ship
+    pub fn is_hit(&self) -> bool {
+        !matches!(self, ShotOutcome::Miss)
+    }
+
+    /// Check weather the shot sank a ship
+    pub fn is_sunk(&self) -> bool {
+        matches!(
+            self,
+            ShotOutcome::Sunk { .. } | ShotOutcome::FleetDestroyed { .. }
+        )
+    }
+
+    /// The kind of ship that was hit, if any
+    pub fn kind(&self) -> Option<&ShipKind> {
+        match self {
+            ShotOutcome::Miss => None,
+            ShotOutcome::Hit { kind }
+            | ShotOutcome::Sunk { kind }
+            | ShotOutcome::FleetDestroyed { kind } => Some(kind),
+        }
+    }
+}
+
+/// A fleet and the shots fired at it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameBoard {
+    pub width: u8,
+    pub height: u8,
+    /// Indexed by row, then column
+    pub board: Vec<Vec<GridState>>,
+    pub ships: Vec<Ship>,
+    pub ships_left: u8,
+}
+
+impl GameBoard {
+    /// Create a board with no shots fired at it
+    pub fn build(ships: Vec<Ship>, rules: &Rules) -> Self {
+        Self {
+            width: rules.width,
+            height: rules.height,
+            board: vec![vec![GridState::Blank; rules.width as usize]; rules.height as usize],
+            ships_left: ships.len() as u8,
+            ships,
+        }
+    }
+
+    /// What the side firing at the board knows about it
+    pub fn view(&self) -> BoardView {
+        BoardView {
+            width: self.width,
+            height: self.height,
+            board: self.board.clone(),
+            fleet: self.ships.iter().map(|ship| ship.kind.clone()).collect(),
+            sunk: self
+                .ships
+                .iter()
+                .filter(|ship| ship.is_sunk())
+                .map(|ship| SunkReport {
+                    kind: ship.kind.clone(),
+                    at: ship.hit_points.last().expect("Sunk ships were hit").clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Records a shot at the board
+    pub fn strike(&mut self, target: &Point) -> Result<ShotOutcome, &'static str> {
+        if target.0 >= self.width || target.1 >= self.height {
+            return Err("Out of bounds.");
+        }
+        if self.board[target.1 as usize][target.0 as usize] != GridState::Blank {
+            return Err("Duplicate strike.");
+        }
+        let Some(hit_ship) = self.ships.iter_mut().find(|ship| ship.is_hit_by(target)) else {
+            self.board[target.1 as usize][target.0 as usize] = GridState::Miss;
+            return Ok(ShotOutcome::Miss);
+        };
+        self.board[target.1 as usize][target.0 as usize] = GridState::Hit;
+        hit_ship.hit(target.clone());
+        let kind = hit_ship.kind.clone();
+        if !hit_ship.is_sunk() {
+            return Ok(ShotOutcome::Hit { kind });
+        }
+        self.ships_left -= 1;
+        if self.ships_left == 0 {
+            Ok(ShotOutcome::FleetDestroyed { kind })
+        } else {
+            Ok(ShotOutcome::Sunk { kind })
+        }
+    }
+
+    /// Rebuilds a board read back from a file from the rules and its ships' positions, and
+    /// checks the shots marked on it agree with the hits on the ships
+    pub fn rebuild(&self, rules: &Rules) -> Result<GameBoard, &'static str> {
+        let mut board = GameBoard::build(rebuild_fleet(rules, &self.ships)?, rules);
+        if self.board.len() != rules.height as usize
+            || self
+                .board
+                .iter()
+                .any(|row| row.len() != rules.width as usize)
+        {
+            return Err("The grid doesn't match the board size.");
+        }
+        for (ship, saved) in board.ships.iter_mut().zip(&self.ships) {
+            for point in &saved.hit_points {
+                if !ship.is_hit_by(point) || ship.hit_points.contains(point) {
+                    return Err("A ship was hit where it isn't.");
+                }
+                ship.hit(point.clone());
+            }
+        }
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let point = Point(x as u8, y as u8);
+                let ship = board.ships.iter().find(|ship| ship.is_hit_by(&point));
+                let is_hit = ship.is_some_and(|ship| ship.hit_points.contains(&point));
+                let agrees = match cell {
+                    GridState::Hit => is_hit,
+                    GridState::Miss => ship.is_none(),
+                    GridState::Blank => !is_hit,
+                };
+                if !agrees {
+                    return Err("The grid doesn't match the hits on the ships.");
+                }
+            }
+        }
+        board.board = self.board.clone();
+        board.ships_left = board.ships.iter().filter(|ship| !ship.is_sunk()).count() as u8;
+        Ok(board)
+    }
+
+    /// Marks the water around the ship sunk at `target` as missed. Used when ships may not
+    /// touch, since no other ship can be there
+    pub fn mark_around_sunk(&mut self, target: &Point) {
+        let Some(ship) = self
+            .ships
+            .iter()
+            .find(|ship| ship.is_hit_by(target) && ship.is_sunk())
+        else {
+            return;
+        };
+        mark_around(&mut self.board, &ship.points);
+    }
+}
+
+/// Places a fleet that was read back again from each ship's position and direction, so
+/// nothing else it claims is trusted. The ships must be the rules' fleet, in order, and
+/// may not overlap, or touch when the rules keep them apart
+pub fn rebuild_fleet(rules: &Rules, fleet: &[Ship]) -> Result<Vec<Ship>, &'static str> {
+    if fleet.len() != rules.fleet.len() {
+        return Err("Wrong number of ships.");
+    }
+    let mut ships: Vec<Ship> = vec![];
+    for (ship, kind) in fleet.iter().zip(&rules.fleet) {
+        if ship.kind != *kind {
+            return Err("Wrong kind of ship.");
+        }
+        let ship = Ship::build(ship.kind.clone(), ship.x, ship.y, ship.direction, rules)?;
+        if ships.iter().any(|placed| placed.does_intercept(&ship)) {
+            return Err("Ships overlap.");
+        }
+        if rules.no_touching && ships.iter().any(|placed| placed.does_touch(&ship)) {
+            return Err("Ships touch.");
+        }
+        ships.push(ship);
+    }
+    Ok(ships)
+}
+
+/// Marks the blank cells next to any of the `points`, diagonally too, as missed
+pub fn mark_around(board: &mut [Vec<GridState>], points: &[Point]) {
+    for point in points {
+        for y in point.1.saturating_sub(1)..=point.1.saturating_add(1) {
+            for x in point.0.saturating_sub(1)..=point.0.saturating_add(1) {
+                if let Some(cell) = board
+                    .get_mut(y as usize)
+                    .and_then(|row| row.get_mut(x as usize))
+                    && *cell == GridState::Blank
+                {
+                    *cell = GridState::Miss;
+                }
+            }
+        }
+    }
+}
+
+/// How the ships of a random layout are arranged
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placement {
+    /// Every ship faces the same way
+    Aligned,
+    /// Any position and direction
+    Random,
+    /// Ships never touch, not even diagonally
+    Spaced,
+    /// Spaced, with ships kept away from where a hunter looks first
+    Hidden,
+}
+
+impl Placement {
+    /// Every arrangement
+    pub const ALL: [Placement; 4] = [
+        Placement::Aligned,
+        Placement::Random,
+        Placement::Spaced,
+        Placement::Hidden,
+    ];
+
+    /// Name of the arrangement
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Placement::Aligned => "Aligned",
+            Placement::Random => "Random",
+            Placement::Spaced => "Spaced",
+            Placement::Hidden => "Hidden",
+        }
+    }
+
+    /// Finds an arrangement by name, ignoring case
+    pub fn parse(name: &str) -> Result<Self, &'static str> {
+        Self::ALL
+            .into_iter()
+            .find(|placement| placement.get_name().eq_ignore_ascii_case(name.trim()))
+            .ok_or("Unknown placement.")
+    }
+}
+
+/// Create a random ship layout
+pub fn generate_game_board(rules: &Rules, rng: &mut impl Rng) -> GameBoard {
+    generate_layout(rules, Placement::Random, rng)
+}
+
+/// Create a random ship layout arranged by `placement`
+pub fn generate_layout(rules: &Rules, placement: Placement, rng: &mut impl Rng) -> GameBoard {
+    if placement == Placement::Hidden {
+        return generate_hidden_layout(rules, rng);
+    }
+    // Small boards can leave no room for the last ships, so start over when that happens.
+    // Give up on the arrangement if it keeps failing, since some fleets can't satisfy it
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let placement = if attempts > 100 {
+            Placement::Random
+        } else {
+            placement
+        };
+        if let Some(ships) = place_fleet(rules, vec![], placement, rng) {
+            return GameBoard::build(ships, rules);
+        }
+    }
+}
+
+/// Randomly places the rest of the fleet around ships that are already placed, such as the
+/// ones a player set by hand. Gives `None` if the rest of the fleet can't be fitted in
+pub fn complete_layout(rules: &Rules, placed: &[Ship], rng: &mut impl Rng) -> Option<GameBoard> {
+    (0..100)
+        .find_map(|_| place_fleet(rules, placed.to_vec(), Placement::Random, rng))
+        .map(|ships| GameBoard::build(ships, rules))
+}
+
+/// Places each ship of the fleet after the ones given at a random free position.
+/// Gives `None` if a ship is left with nowhere to go
+fn place_fleet(
+    rules: &Rules,
+    mut ships: Vec<Ship>,
+    placement: Placement,
+    rng: &mut impl Rng,
+) -> Option<Vec<Ship>> {
+    let aligned_direction = random_direction(rng);
+    for ship_kind in rules.fleet.iter().skip(ships.len()) {
+        let direction = if placement == Placement::Aligned {
+            aligned_direction
+        } else {
+            random_direction(rng)
+        };
+        // Now go through every position and see if a ship can be placed there
+        let mut possible_positions: Vec<Point> = vec![];
+        for i in 0..rules.height {
+            for j in 0..rules.width {
+                if Ship::can_exist(ship_kind, j, i, &direction, rules) {
+                    let new_ship = Ship::build(ship_kind.clone(), j, i, direction, rules)
+                        .expect("Somehow, the ship can't exist");
+                    let keep_apart = placement == Placement::Spaced || rules.no_touching;
+                    let is_blocked = ships.iter().any(|ship| {
+                        ship.does_intercept(&new_ship) || (keep_apart && ship.does_touch(&new_ship))
+                    });
+                    if !is_blocked {
+                        possible_positions.push(Point(j, i))
+                    }
+                }
+            }
+        }
+        if possible_positions.is_empty() {
+            return None;
+        }
+
+        let random_position = &possible_positions[rng.random_range(0..possible_positions.len())];
+        ships.push(
+            Ship::build(
+                ship_kind.clone(),
+                random_position.0,
+                random_position.1,
+                direction,
+                rules,
+            )
+            .expect("Somehow, the ship can't exist"),
+        );
+    }
+    Some(ships)
+}
+
+/// Picks the least predictable of several spaced layouts: the one whose ships sit on the
+/// cells a density hunter would reach last
+fn generate_hidden_layout(rules: &Rules, rng: &mut impl Rng) -> GameBoard {
+    let blank = vec![vec![GridState::Blank; rules.width as usize]; rules.height as usize];
+    let lengths: Vec<u8> = rules.fleet.iter().map(|kind| kind.get_len()).collect();
+    let density = density_map(&blank, &[], &lengths);
+    let score = |board: &GameBoard| -> u32 {
+        board
+            .ships
+            .iter()
+            .flat_map(|ship| ship.points.iter())
+            .map(|point| density[point.1 as usize][point.0 as usize])
+            .sum()
+    };
+    (0..20)
+        .map(|_| generate_layout(rules, Placement::Spaced, rng))
+        .min_by_key(score)
+        .expect("At least one layout is generated")
+}
+
+/// Generate random direction
+fn random_direction(rng: &mut impl Rng) -> ShipDirection {
+    match rng.random_range(0..4) {
+        0 => ShipDirection::Down,
+        1 => ShipDirection::Up,
+        3 => ShipDirection::Left,
+        _ => ShipDirection::Right,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::{parse_fleet, standard_fleet};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn strikes_are_recorded() {
+        let rules = Rules::default();
+        let kind = ShipKind::build("Destroyer", 2).unwrap();
+        let ships = vec![
+            Ship::build(kind.clone(), 0, 0, ShipDirection::Up, &rules).unwrap(),
+            Ship::build(kind.clone(), 5, 0, ShipDirection::Up, &rules).unwrap(),
+        ];
+        let mut board = GameBoard::build(ships, &rules);
+
+        assert_eq!(board.strike(&Point(5, 5)), Ok(ShotOutcome::Miss));
+        assert_eq!(board.board[5][5], GridState::Miss);
+        assert_eq!(board.strike(&Point(5, 5)), Err("Duplicate strike."));
+        assert_eq!(board.strike(&Point(10, 0)), Err("Out of bounds."));
+
+        assert_eq!(board.strike(&Point(0, 0)), Ok(ShotOutcome::Hit { kind: kind.clone() }));
+        assert_eq!(board.ships_left, 2);
+        assert_eq!(board.strike(&Point(0, 1)), Ok(ShotOutcome::Sunk { kind: kind.clone() }));
+        assert_eq!(board.board[1][0], GridState::Hit);
+        assert_eq!(board.ships_left, 1);
+        assert_eq!(board.strike(&Point(5, 0)), Ok(ShotOutcome::Hit { kind: kind.clone() }));
+        assert_eq!(board.strike(&Point(5, 1)), Ok(ShotOutcome::FleetDestroyed { kind }));
+        assert_eq!(board.ships_left, 0);
+    }
+
+    #[test]
+    fn generated_ships_do_not_overlap() {
+        let fleet = parse_fleet("Dreadnought:6, Destroyer:2, Destroyer:2").unwrap();
+        for rules in [
+            Rules::default(),
+            Rules::build(6, 6, standard_fleet()).unwrap(),
+            Rules::build(20, 8, standard_fleet()).unwrap(),
+            Rules::build(8, 8, fleet).unwrap(),
+            Rules {
+                no_touching: true,
+                ..Rules::default()
+            },
+        ] {
+            let board = generate_game_board(&rules, &mut rand::rng());
+            assert_eq!(board.ships.len(), rules.fleet.len());
+            assert_eq!(board.board.len(), rules.height as usize);
+            assert_eq!(board.board[0].len(), rules.width as usize);
+            for (i, ship) in board.ships.iter().enumerate() {
+                assert!(ship.points.iter().all(|point| point.is_within(&rules)));
+                assert!(!board.ships[i + 1..].iter().any(|other| ship.does_intercept(other)));
+                if rules.no_touching {
+                    assert!(!board.ships[i + 1..].iter().any(|other| ship.does_touch(other)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spaced_layouts_do_not_touch() {
+        let rules = Rules::default();
+        let board = generate_layout(&rules, Placement::Spaced, &mut StdRng::seed_from_u64(5));
+        for (i, ship) in board.ships.iter().enumerate() {
+            assert!(!board.ships[i + 1..].iter().any(|other| ship.does_touch(other)));
+        }
+    }
+
+    #[test]
+    fn seeded_layouts_are_reproducible() {
+        let rules = Rules::default();
+        let first = generate_game_board(&rules, &mut StdRng::seed_from_u64(7));
+        let second = generate_game_board(&rules, &mut StdRng::seed_from_u64(7));
+        for (a, b) in first.ships.iter().zip(second.ships.iter()) {
+            assert_eq!(a.points, b.points);
+        }
+    }
+
+    #[test]
+    fn layouts_are_completed_around_placed_ships() {
+        let rules = Rules::default();
+        let mut rng = StdRng::seed_from_u64(9);
+        let placed = vec![
+            Ship::build(rules.fleet[0].clone(), 0, 0, ShipDirection::Up, &rules).unwrap(),
+            Ship::build(rules.fleet[1].clone(), 2, 0, ShipDirection::Up, &rules).unwrap(),
+        ];
+        let board = complete_layout(&rules, &placed, &mut rng).unwrap();
+        assert_eq!(board.ships.len(), rules.fleet.len());
+        for (ship, placed) in board.ships.iter().zip(&placed) {
+            assert_eq!(ship.points, placed.points);
+        }
+        for (i, ship) in board.ships.iter().enumerate() {
+            assert_eq!(ship.kind, rules.fleet[i]);
+            assert!(!board.ships[i + 1..].iter().any(|other| ship.does_intercept(other)));
+        }
+    }
+}
@@ -0,0 +1,471 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::board::{GameBoard, GridState, Placement, generate_layout};
+use super::rules::Rules;
+use super::ship::Point;
+use super::view::BoardView;
+
+/// How strong the Admiral plays
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Difficulty {
+    /// Fires at random and places ships all facing one way
+    Easy,
+    /// Hunts at random and chases hits
+    #[default]
+    Normal,
+    /// Hunts on a checkerboard, chases hits and never lets ships touch
+    Hard,
+    /// Fires at the most likely cell and hides ships where they are least expected
+    Expert,
+}
+
+impl Difficulty {
+    /// Every level, from easiest to hardest
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+
+    /// Name of the level
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Expert => "Expert",
+        }
+    }
+
+    /// Finds a level by name, ignoring case
+    pub fn parse(name: &str) -> Result<Self, &'static str> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.get_name().eq_ignore_ascii_case(name.trim()))
+            .ok_or("Unknown difficulty.")
+    }
+
+    /// The next level up, wrapping around to the easiest
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|level| level == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// The next level down, wrapping around to the hardest
+    pub fn previous(&self) -> Self {
+        let index = Self::ALL.iter().position(|level| level == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// How the level fires
+    pub fn targeting(&self) -> Targeting {
+        match self {
+            Difficulty::Easy => Targeting::Random,
+            Difficulty::Normal => Targeting::Hunt,
+            Difficulty::Hard => Targeting::Parity,
+            Difficulty::Expert => Targeting::Density,
+        }
+    }
+
+    /// How the level lays out its fleet
+    pub fn placement(&self) -> Placement {
+        match self {
+            Difficulty::Easy => Placement::Aligned,
+            Difficulty::Normal => Placement::Random,
+            Difficulty::Hard => Placement::Spaced,
+            Difficulty::Expert => Placement::Hidden,
+        }
+    }
+
+    /// Choose where to fire next
+    pub fn gen_target(&self, target_board: &BoardView, rng: &mut impl Rng) -> Point {
+        self.targeting().gen_target(target_board, rng)
+    }
+
+    /// Choose every shot of a salvo
+    pub fn gen_volley(
+        &self,
+        target_board: &BoardView,
+        shots: usize,
+        rng: &mut impl Rng,
+    ) -> Vec<Point> {
+        self.targeting().gen_volley(target_board, shots, rng)
+    }
+
+    /// Create the Admiral's ship layout
+    pub fn generate_game_board(&self, rules: &Rules, rng: &mut impl Rng) -> GameBoard {
+        generate_layout(rules, self.placement(), rng)
+    }
+}
+
+/// A way of choosing where to fire
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Targeting {
+    /// `gen_random_target`
+    Random,
+    /// `gen_bot_target`
+    Hunt,
+    /// `gen_parity_target`
+    Parity,
+    /// `gen_density_target`
+    Density,
+}
+
+impl Targeting {
+    /// Every way of targeting
+    pub const ALL: [Targeting; 4] = [
+        Targeting::Random,
+        Targeting::Hunt,
+        Targeting::Parity,
+        Targeting::Density,
+    ];
+
+    /// Name of the targeting
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Targeting::Random => "Random",
+            Targeting::Hunt => "Hunt",
+            Targeting::Parity => "Parity",
+            Targeting::Density => "Density",
+        }
+    }
+
+    /// Finds a targeting by name, ignoring case
+    pub fn parse(name: &str) -> Result<Self, &'static str> {
+        Self::ALL
+            .into_iter()
+            .find(|targeting| targeting.get_name().eq_ignore_ascii_case(name.trim()))
+            .ok_or("Unknown targeting.")
+    }
+
+    /// Choose where to fire next
+    pub fn gen_target(&self, target_board: &BoardView, rng: &mut impl Rng) -> Point {
+        match self {
+            Targeting::Random => gen_random_target(target_board, rng),
+            Targeting::Hunt => gen_bot_target(target_board, rng),
+            Targeting::Parity => gen_parity_target(target_board, rng),
+            Targeting::Density => gen_density_target(target_board, rng),
+        }
+    }
+
+    /// Choose every shot of a salvo. Each planned shot is treated as a miss while planning
+    /// the rest, so the volley spreads out instead of piling onto one cell
+    pub fn gen_volley(
+        &self,
+        target_board: &BoardView,
+        shots: usize,
+        rng: &mut impl Rng,
+    ) -> Vec<Point> {
+        let mut planning = target_board.clone();
+        (0..shots)
+            .map(|_| {
+                let target = self.gen_target(&planning, rng);
+                planning.board[target.1 as usize][target.0 as usize] = GridState::Miss;
+                target
+            })
+            .collect()
+    }
+}
+
+/// Fire at any cell that has not been struck
+pub fn gen_random_target(target_board: &BoardView, rng: &mut impl Rng) -> Point {
+    let possible_positions: Vec<Point> = (0..target_board.height)
+        .flat_map(|y| (0..target_board.width).map(move |x| Point(x, y)))
+        .filter(|point| target_board.get(point) == GridState::Blank)
+        .collect();
+    possible_positions[rng.random_range(0..possible_positions.len())].clone()
+}
+
+/// Randomly generate the target for the bot
+pub fn gen_bot_target(target_board: &BoardView, rng: &mut impl Rng) -> Point {
+    hunt_and_target(target_board, rng, false)
+}
+
+/// Like `gen_bot_target`, but hunts only on cells a checkerboard apart, spaced by the
+/// shortest ship still afloat, since every ship must cover one of them
+pub fn gen_parity_target(target_board: &BoardView, rng: &mut impl Rng) -> Point {
+    hunt_and_target(target_board, rng, true)
+}
+
+/// Chases hits on unsunk ships, otherwise hunts for a new one
+fn hunt_and_target(target_board: &BoardView, rng: &mut impl Rng, parity: bool) -> Point {
+    let mut possible_positions: Vec<Point> = vec![];
+    let mut recommended_positions: Vec<Point> = vec![];
+    let (width, height) = (target_board.width as usize, target_board.height as usize);
+    let sunk_points = target_board.sunk_points();
+    let is_open_hit = |x: usize, y: usize| {
+        target_board.board[y][x] == GridState::Hit
+            && !sunk_points.contains(&Point(x as u8, y as u8))
+    };
+    for i in 0..width {
+        for j in 0..height {
+            match target_board.board[j][i] {
+                GridState::Blank => possible_positions.push(Point(i as u8, j as u8)),
+                GridState::Hit => {
+                    // Prioritize hitting near ships that are hit but not sunk
+                    // If we have hit twice in a row, then continue linearly
+                    if !is_open_hit(i, j) {
+                        continue;
+                    }
+                    // Look Up
+                    if j > 0 && target_board.board[j - 1][i] == GridState::Blank {
+                        let up = Point(i as u8, (j - 1) as u8);
+                        // If down was hit too, then up most likely continues the ship
+                        if j + 1 < height && is_open_hit(i, j + 1) {
+                            return up
+                        }
+                        recommended_positions.push(up);
+                    }
+                    // Look Down
+                    if j + 1 < height && target_board.board[j + 1][i] == GridState::Blank {
+                        let down = Point(i as u8, (j + 1) as u8);
+                        // If up was hit too, then down most likely continues the ship
+                        if j > 0 && is_open_hit(i, j - 1) {
+                            return down
+                        }
+                        recommended_positions.push(down);
+                    }
+                    // Look Left
+                    if i > 0 && target_board.board[j][i - 1] == GridState::Blank {
+                        let left = Point((i - 1) as u8, j as u8);
+                        // If right was hit too, then left most likely continues the ship
+                        if i + 1 < width && is_open_hit(i + 1, j) {
+                            return left;
+                        }
+                        recommended_positions.push(left);
+                    }
+                    // Look Right
+                    if i + 1 < width && target_board.board[j][i + 1] == GridState::Blank {
+                        let right = Point((i + 1) as u8, j as u8);
+                        // If left was hit too, then right most likely continues the ship
+                        if i > 0 && is_open_hit(i - 1, j) {
+                            return right;
+                        }
+                        recommended_positions.push(right);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    if !recommended_positions.is_empty() {
+        return recommended_positions[rng.random_range(0..recommended_positions.len())].clone();
+    }
+    if parity {
+        let spacing = target_board
+            .remaining()
+            .iter()
+            .map(|kind| kind.get_len())
+            .min()
+            .unwrap_or(1);
+        let parity_positions: Vec<Point> = possible_positions
+            .iter()
+            .filter(|point| (point.0 as u32 + point.1 as u32).is_multiple_of(spacing as u32))
+            .cloned()
+            .collect();
+        if !parity_positions.is_empty() {
+            possible_positions = parity_positions;
+        }
+    }
+    possible_positions[rng.random_range(0..possible_positions.len())].clone()
+}
+
+/// Fire at the blank cell that the most placements of the unsunk ships could cover
+pub fn gen_density_target(target_board: &BoardView, rng: &mut impl Rng) -> Point {
+    let density = heatmap(target_board);
+    densest_cell(&target_board.board, &density, rng)
+}
+
+/// The density map of a board, from what its view shows of the hits, misses and sinks
+pub fn heatmap(target_board: &BoardView) -> Vec<Vec<u32>> {
+    let remaining: Vec<u8> = target_board
+        .remaining()
+        .iter()
+        .map(|kind| kind.get_len())
+        .collect();
+    density_map(&target_board.board, &target_board.sunk_points(), &remaining)
+}
+
+/// Counts, for every cell, how many placements of the remaining ships could cover it.
+/// Placements may not cross a miss or a sunk ship. While there are hits that no sunk ship
+/// accounts for, only placements through those hits count, weighted by how many they cover
+pub fn density_map(
+    board: &[Vec<GridState>],
+    sunk_points: &[Point],
+    remaining: &[u8],
+) -> Vec<Vec<u32>> {
+    let height = board.len();
+    let width = board.first().map_or(0, |row| row.len());
+    let is_sunk = |x: usize, y: usize| sunk_points.contains(&Point(x as u8, y as u8));
+    let is_open_hit = |x: usize, y: usize| board[y][x] == GridState::Hit && !is_sunk(x, y);
+    let is_blocked = |x: usize, y: usize| board[y][x] == GridState::Miss || is_sunk(x, y);
+
+    let count = |target_mode: bool| {
+        let mut density = vec![vec![0u32; width]; height];
+        for &len in remaining {
+            let len = len as usize;
+            for (dx, dy) in [(1, 0), (0, 1)] {
+                for y in 0..height {
+                    for x in 0..width {
+                        if x + dx * (len - 1) >= width || y + dy * (len - 1) >= height {
+                            continue;
+                        }
+                        let cells: Vec<(usize, usize)> =
+                            (0..len).map(|i| (x + dx * i, y + dy * i)).collect();
+                        if cells.iter().any(|&(x, y)| is_blocked(x, y)) {
+                            continue;
+                        }
+                        let weight = if target_mode {
+                            cells.iter().filter(|&&(x, y)| is_open_hit(x, y)).count() as u32
+                        } else {
+                            1
+                        };
+                        for &(x, y) in cells.iter() {
+                            if board[y][x] == GridState::Blank {
+                                density[y][x] += weight;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        density
+    };
+
+    let has_open_hits = (0..height).any(|y| (0..width).any(|x| is_open_hit(x, y)));
+    if has_open_hits {
+        let density = count(true);
+        if density.iter().flatten().any(|weight| *weight > 0) {
+            return density;
+        }
+    }
+    count(false)
+}
+
+/// Picks the blank cell with the highest density, breaking ties randomly
+fn densest_cell(board: &[Vec<GridState>], density: &[Vec<u32>], rng: &mut impl Rng) -> Point {
+    let mut best: Vec<Point> = vec![];
+    let mut best_weight = 0;
+    for (y, row) in board.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if *cell != GridState::Blank {
+                continue;
+            }
+            let weight = density[y][x];
+            if best.is_empty() || weight > best_weight {
+                best_weight = weight;
+                best.clear();
+            }
+            if weight == best_weight {
+                best.push(Point(x as u8, y as u8));
+            }
+        }
+    }
+    best[rng.random_range(0..best.len())].clone()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::generate_game_board;
+    use crate::rules::Rules;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn seeded_shots_are_reproducible() {
+        let rules = Rules::default();
+        let shots = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut board = generate_game_board(&rules, &mut rng);
+            (0..40)
+                .map(|_| {
+                    let target = gen_bot_target(&board.view(), &mut rng);
+                    board.strike(&target).unwrap();
+                    target
+                })
+                .collect::<Vec<Point>>()
+        };
+        assert_eq!(shots(11), shots(11));
+    }
+
+    #[test]
+    fn density_prefers_the_centre_and_follows_hits() {
+        let blank = vec![vec![GridState::Blank; 10]; 10];
+        let density = density_map(&blank, &[], &[5, 4, 3, 3, 2]);
+        assert!(density[4][4] > density[0][0]);
+        assert!(density[5][5] > density[9][0]);
+
+        // A lone hit is chased through its only open neighbour
+        let mut board = blank.clone();
+        board[4][4] = GridState::Hit;
+        board[4][3] = GridState::Miss;
+        board[4][5] = GridState::Miss;
+        board[3][4] = GridState::Miss;
+        let density = density_map(&board, &[], &[2]);
+        assert_eq!(density[5][4], 1);
+        assert_eq!(density.iter().flatten().sum::<u32>(), 1);
+
+        // Hits of a sunk ship block placements instead of attracting them
+        let density = density_map(&board, &[Point(4, 4)], &[2]);
+        assert_eq!(density[5][4], 3);
+        assert!(density[0][0] > 0);
+    }
+
+    #[test]
+    fn every_difficulty_finishes_a_game() {
+        let rules = Rules::default();
+        let mut rng = StdRng::seed_from_u64(9);
+        for level in Difficulty::ALL {
+            let mut board = level.generate_game_board(&rules, &mut rng);
+            let mut shots = 0;
+            while board.ships_left > 0 {
+                let target = level.gen_target(&board.view(), &mut rng);
+                board.strike(&target).unwrap();
+                shots += 1;
+            }
+            assert!(shots <= 100);
+        }
+        assert_eq!(Difficulty::parse(" hard"), Ok(Difficulty::Hard));
+        assert_eq!(Difficulty::Expert.next(), Difficulty::Easy);
+        assert_eq!(Difficulty::Easy.previous(), Difficulty::Expert);
+    }
+
+    #[test]
+    fn density_target_sinks_every_ship() {
+        let rules = Rules::default();
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut board = generate_game_board(&rules, &mut rng);
+        let mut shots = 0;
+        while board.ships_left > 0 {
+            let target = gen_density_target(&board.view(), &mut rng);
+            board.strike(&target).unwrap();
+            shots += 1;
+        }
+        assert!(shots < 100);
+    }
+
+    #[test]
+    fn volleys_never_repeat_a_cell() {
+        let rules = Rules::default();
+        let mut rng = StdRng::seed_from_u64(11);
+        for level in Difficulty::ALL {
+            let mut board = level.generate_game_board(&rules, &mut rng);
+            while board.ships_left > 0 {
+                // Salvo rules never ask for more shots than there are open cells
+                let open = board.board.iter().flatten();
+                let shots = (board.ships_left as usize)
+                    .min(open.filter(|state| **state == GridState::Blank).count());
+                let volley = level.gen_volley(&board.view(), shots, &mut rng);
+                assert_eq!(volley.len(), shots);
+                for target in volley {
+                    if board.ships_left > 0 {
+                        board.strike(&target).unwrap();
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,128 +1,180 @@
 use crate::terminal_utils::print_center;
 
-use super::ship::{Point, Ship, ShipDirection, ShipKind};
-use super::terminal_utils::create_colored_grid;
+use super::mouse::{self, Button, Input};
+use super::terminal_utils::{create_colored_grid, row_label_width};
+use cl_battleship::board::{GameBoard, complete_layout};
+use cl_battleship::rules::Rules;
+use cl_battleship::ship::{Point, Ship, ShipKind};
 use console::{style, Key, Style, Term};
-use rand::{self, Rng};
-#[derive(Clone, Copy, PartialEq)]
-pub enum GridState {
-    Blank,
-    Hit,
-    Miss,
-}
-pub struct GameBoard {
-    pub board: [[GridState; 10]; 10],
-    pub ships: Vec<Ship>,
-    pub ships_left: u8,
-}
-
-impl GameBoard {
-    fn build(ships: Vec<Ship>) -> Self {
-        Self {
-            board: [[GridState::Blank; 10]; 10],
-            ships,
-            ships_left: 5,
-        }
-    }
-}
 
-// TODO: handle r for restart, or possibly a,1 for ship positions
 /// Sets up the users board
-pub fn start() -> GameBoard {
+pub fn start(rules: &Rules) -> GameBoard {
     let term = Term::buffered_stdout();
-    let mut ships: Vec<Ship> = vec![];
-    render(&term, &ships);
-    term.write_line("\n");
-    [
-        ShipKind::Carrier,
-        ShipKind::Battleship,
-        ShipKind::Cruiser,
-        ShipKind::Submarine,
-        ShipKind::Destroyer,
-    ]
-    .iter()
-    .for_each(|ship_kind| {
-        // Create the ship
-        let ship = Ship::build(*ship_kind, 0, 0, ShipDirection::Up).unwrap();
-        ships.push(ship);
-        loop {
-            term.clear_last_lines(25);
-            print_center(&term, &format!("{}", style("Set Up").bold()));
-            print_center(&term, &format!("Use your {} keys to move the ship, {} to rotate, and {} to set its position.", style("Arrow").bold(), style("Space").bold(), style("Enter").bold()));
-            render(&term, &ships);
-            let key = term.read_key();
-            match key {
-                Ok(Key::Char(' ')) => ships.last_mut().unwrap().rotate(),
-                Ok(Key::ArrowUp) | Ok(Key::Char('w'))|  Ok(Key::Char('W')) => ships.last_mut().unwrap().move_up(),
-                Ok(Key::ArrowDown) | Ok(Key::Char('s'))|  Ok(Key::Char('S')) => ships.last_mut().unwrap().move_down(),
-                Ok(Key::ArrowLeft)| Ok(Key::Char('a'))|  Ok(Key::Char('A')) => ships.last_mut().unwrap().move_left(),
-                Ok(Key::ArrowRight) | Ok(Key::Char('d'))|  Ok(Key::Char('D'))=> ships.last_mut().unwrap().move_right(),
-                Ok(Key::Enter) => {
-                    if let Some(last_ship) = ships.last() {
-                        let does_collide = ships.iter().any(|ship| {
-                            ship as *const _ != last_ship as *const _
-                                && ship.does_intercept(last_ship)
-                        });
-                        if !does_collide {
-                            break;
-                        }
+    let mut rng = rand::rng();
+    // The ships set so far, then the one being placed
+    let mut ships: Vec<Ship> = vec![new_ship(rules, 0)];
+    // Once the rest of the fleet is placed at random, how many ships were placed by hand
+    let mut shuffled: Option<usize> = None;
+    let mut notice = None;
+    // The title, the instructions, the legend and the grid
+    let setup_lines = 2 * rules.height as usize + 6;
+    mouse::anchor(&term);
+    term.write_str(&"\n".repeat(setup_lines));
+    loop {
+        term.clear_last_lines(setup_lines);
+        let ship_kind = ships.last().unwrap().kind.clone();
+        if shuffled.is_some() {
+            print_center(
+                &term,
+                &format!("{} - The rest of your fleet was placed at random", style("Set Up").bold()),
+            );
+            print_center(&term, &format!("Press {} to start, or {} to place them again.", style("Enter").bold(), style("R").bold()));
+        } else {
+            print_center(
+                &term,
+                &format!(
+                    "{} - Place your {} ({} long)",
+                    style("Set Up").bold(),
+                    style(ship_kind.get_name()).bold(),
+                    ship_kind.get_len()
+                ),
+            );
+            print_center(&term, &format!("Use your {} keys or click to move the ship, {} or right click to rotate, and {} to set its position.", style("Arrow").bold(), style("Space").bold(), style("Enter").bold()));
+        }
+        print_center(
+            &term,
+            &match notice.take() {
+                Some(error) => format!("{} {}", style(" Error ").on_red().bold(), error),
+                None => legend(),
+            },
+        );
+        render(&term, &ships, rules);
+        let key = match mouse::read_input(&term) {
+            Ok(Input::Click { button, column, row }) => {
+                if let (None, Some(cell)) = (shuffled, clicked_cell(&term, (column, row), rules)) {
+                    let ship = ships.last_mut().unwrap();
+                    match button {
+                        Button::Left => move_to(ship, cell, rules),
+                        Button::Right => ship.rotate(rules),
                     }
                 }
-                _ => (),
+                continue;
             }
-        }
-    });
-    term.clear_last_lines(25);
-    term.flush();
-
-    GameBoard::build(ships)
-}
-
-/// Create a random ship layout
-pub fn generate_game_board() -> GameBoard {
-    let mut rng = rand::rng();
-    let ships: Vec<Ship> = [
-        ShipKind::Carrier,
-        ShipKind::Battleship,
-        ShipKind::Cruiser,
-        ShipKind::Submarine,
-        ShipKind::Destroyer,
-    ]
-    .iter()
-    .fold(vec![], |mut ships, ship_kind| {
-        // Generate random direction
-        let direction = match rng.random_range(0..4) {
-            0 => ShipDirection::Down,
-            1 => ShipDirection::Up,
-            3 => ShipDirection::Left,
-            _ => ShipDirection::Right,
+            Ok(Input::Key(key)) => Ok(key),
+            Err(error) => Err(error),
         };
-        // Now go through every position and see if a ship can be placed there
-        let mut possible_positions: Vec<Point> = vec![];
-        for i in 0..9 {
-            for j in 0..9 {
-                if Ship::can_exist(ship_kind, j, i, &direction) {
-                    let new_ship = Ship::build(*ship_kind, j, i, direction)
-                        .expect("Somehow, the ship can't exist");
-                    if !ships.iter().any(|ship| ship.does_intercept(&new_ship)) {
-                        possible_positions.push(Point(j, i))
+        // Whether the ship being placed should be set in place
+        let mut set = false;
+        match key {
+            Ok(Key::Char('r')) | Ok(Key::Char('R')) => {
+                // The ship being placed is placed at random too
+                let placed_by_hand = shuffled.unwrap_or(ships.len() - 1);
+                match complete_layout(rules, &ships[..placed_by_hand], &mut rng) {
+                    Some(board) => {
+                        ships = board.ships;
+                        shuffled = Some(placed_by_hand);
                     }
+                    None => notice = Some("The rest of the fleet doesn't fit around your ships."),
+                }
+            }
+            Ok(Key::Char('u')) | Ok(Key::Char('U')) | Ok(Key::Backspace) => {
+                if let Some(placed_by_hand) = shuffled.take() {
+                    ships.truncate(placed_by_hand);
+                    ships.push(new_ship(rules, placed_by_hand));
+                } else if ships.len() > 1 {
+                    // The last ship set can be moved again
+                    ships.pop();
                 }
             }
+            Ok(Key::Char('c')) | Ok(Key::Char('C')) => {
+                ships = vec![new_ship(rules, 0)];
+                shuffled = None;
+            }
+            Ok(Key::Enter) if shuffled.is_some() => break,
+            _ if shuffled.is_some() => (),
+            Ok(Key::Char(' ')) => ships.last_mut().unwrap().rotate(rules),
+            Ok(Key::ArrowUp) | Ok(Key::Char('w'))|  Ok(Key::Char('W')) => ships.last_mut().unwrap().move_up(rules),
+            Ok(Key::ArrowDown) | Ok(Key::Char('s'))|  Ok(Key::Char('S')) => ships.last_mut().unwrap().move_down(rules),
+            Ok(Key::ArrowLeft)| Ok(Key::Char('a'))|  Ok(Key::Char('A')) => ships.last_mut().unwrap().move_left(rules),
+            Ok(Key::ArrowRight) | Ok(Key::Char('d'))|  Ok(Key::Char('D'))=> ships.last_mut().unwrap().move_right(rules),
+            Ok(Key::Enter) => set = true,
+            Ok(Key::Tab) => match type_placement(&term, &ship_kind, rules) {
+                Ok(ship) => {
+                    *ships.last_mut().unwrap() = ship;
+                    set = true;
+                }
+                Err(error) => notice = Some(error),
+            },
+            _ => (),
+        }
+        if set {
+            let (last_ship, placed) = ships.split_last().unwrap();
+            if placed.iter().any(|ship| ship.does_intercept(last_ship)) {
+                notice = Some("That overlaps another ship.");
+            } else if rules.no_touching && placed.iter().any(|ship| ship.does_touch(last_ship)) {
+                notice = Some("Ships may not touch, even diagonally.");
+            } else if ships.len() == rules.fleet.len() {
+                break;
+            } else {
+                ships.push(new_ship(rules, ships.len()));
+            }
         }
+    }
+    term.clear_last_lines(setup_lines);
+    term.flush();
 
-        let random_position = &possible_positions[rng.random_range(0..possible_positions.len())];
-        ships.push(
-            Ship::build(*ship_kind, random_position.0, random_position.1, direction)
-                .expect("Somehow, the ship can't exist"),
-        );
-        ships
-    });
-    GameBoard::build(ships)
+    GameBoard::build(ships, rules)
+}
+
+/// A new ship of the fleet, waiting to be placed in the top left corner
+fn new_ship(rules: &Rules, index: usize) -> Ship {
+    Ship::start(rules.fleet[index].clone(), rules)
+}
+
+/// Asks where to place a ship, such as "A1 down" or "A1-A5"
+fn type_placement(term: &Term, kind: &ShipKind, rules: &Rules) -> Result<Ship, &'static str> {
+    term.write_line(&format!(
+        "Type where to place your {}, such as {} or {}: ",
+        kind.get_name(),
+        style("A1 down").bold(),
+        style(format!("A1-A{}", kind.get_len())).bold()
+    ));
+    term.flush();
+    let input = term.read_line().unwrap_or_default();
+    term.clear_last_lines(2);
+    Ship::parse(kind.clone(), &input, rules)
+}
+
+/// The keys that change more than the ship being placed
+fn legend() -> String {
+    format!(
+        "{} Type position   {} Random fleet   {} Undo   {} Clear",
+        style(" Tab ").on_blue().bold(),
+        style(" R ").on_blue().bold(),
+        style(" U ").on_blue().bold(),
+        style(" C ").on_blue().bold()
+    )
+}
+
+/// Finds the cell under a click on the centred grid, which ends just above the cursor
+fn clicked_cell(term: &Term, click: (usize, usize), rules: &Rules) -> Option<Point> {
+    let (_, width) = term.size();
+    // Each line is centred on its own, so use the width of a row's line
+    let line_width = row_label_width(rules.height) + 4 * rules.width as usize;
+    let indent = (width as usize).saturating_sub(line_width) / 2;
+    let grid_lines = 2 * rules.height as usize + 3;
+    mouse::grid_cell(term, click, grid_lines, indent, (rules.width, rules.height))
+}
+
+/// Moves a ship so it starts at the cell, if it fits there
+fn move_to(ship: &mut Ship, Point(x, y): Point, rules: &Rules) {
+    if let Ok(moved) = Ship::build(ship.kind.clone(), x, y, ship.direction, rules) {
+        *ship = moved;
+    }
 }
 
 /// Renders a battleship grid
-pub fn render(term: &Term, ships: &[Ship]) {
+pub fn render(term: &Term, ships: &[Ship], rules: &Rules) {
     let last_ship_index = if !ships.is_empty() {
         ships.len() - 1
     } else {
@@ -138,9 +190,9 @@ pub fn render(term: &Term, ships: &[Ship]) {
     } else {
         &vec![]
     };
-    let coloured_grid: Vec<Vec<Style>> = (0..10)
+    let coloured_grid: Vec<Vec<Style>> = (0..rules.height)
         .map(|i| {
-            (0..10)
+            (0..rules.width)
                 .map(|j| {
                     if last_ship_points.contains(&Point(j, i)) && ship_points.contains(&&Point(j, i))
                     {
@@ -0,0 +1,434 @@
+use super::board::{GameBoard, GridState, ShotOutcome, rebuild_fleet};
+use super::rules::Rules;
+use super::ship::Point;
+use serde::{Deserialize, Serialize};
+
+/// One of the two sides of a game
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Side {
+    Player,
+    Opponent,
+}
+
+impl Side {
+    /// The side being shot at by this side
+    pub fn other(&self) -> Side {
+        match self {
+            Side::Player => Side::Opponent,
+            Side::Opponent => Side::Player,
+        }
+    }
+}
+
+/// The state of a game: its rules, two boards, whose turn it is, and who has won
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Game {
+    rules: Rules,
+    pub player_board: GameBoard,
+    pub opponent_board: GameBoard,
+    turn: Side,
+    winner: Option<Side>,
+    /// Every shot fired so far. Saves from before shots were recorded have none
+    #[serde(default)]
+    history: Vec<ShotRecord>,
+}
+
+/// A shot that was fired, and what it did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShotRecord {
+    /// Counts from 1. Every shot of a salvo shares a turn
+    pub turn: u32,
+    pub shooter: Side,
+    pub target: Point,
+    pub outcome: ShotOutcome,
+}
+
+impl Game {
+    /// Create a game where the player shoots first
+    pub fn new(rules: Rules, player_board: GameBoard, opponent_board: GameBoard) -> Self {
+        Self {
+            rules,
+            player_board,
+            opponent_board,
+            turn: Side::Player,
+            winner: None,
+            history: vec![],
+        }
+    }
+
+    /// The rules the game is played by
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// The side that fires next
+    pub fn turn(&self) -> Side {
+        self.turn
+    }
+
+    /// The side that sunk the whole enemy fleet, if any
+    pub fn winner(&self) -> Option<Side> {
+        self.winner
+    }
+
+    /// Every shot fired so far, in order
+    pub fn history(&self) -> &[ShotRecord] {
+        &self.history
+    }
+
+    /// Check weather the game has been won
+    pub fn is_game_over(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    /// The board holding a side's fleet
+    pub fn board(&self, side: Side) -> &GameBoard {
+        match side {
+            Side::Player => &self.player_board,
+            Side::Opponent => &self.opponent_board,
+        }
+    }
+
+    fn board_mut(&mut self, side: Side) -> &mut GameBoard {
+        match side {
+            Side::Player => &mut self.player_board,
+            Side::Opponent => &mut self.opponent_board,
+        }
+    }
+
+    /// Shots the current side fires this turn
+    pub fn shots_per_turn(&self) -> usize {
+        shots_per_turn(
+            &self.rules,
+            self.board(self.turn).ships_left,
+            &self.board(self.turn.other()).board,
+        )
+    }
+
+    /// Rebuilds a game read back from a file, checking its rules, both fleets and the shots
+    /// fired all agree, so a damaged or edited game is refused rather than breaking play
+    pub fn rebuild(self) -> Result<Self, &'static str> {
+        self.rules.check()?;
+        let player_board = self.player_board.rebuild(&self.rules)?;
+        let opponent_board = self.opponent_board.rebuild(&self.rules)?;
+        // Only the side that sank the whole other fleet can have won
+        let winner = if opponent_board.ships_left == 0 {
+            Some(Side::Player)
+        } else if player_board.ships_left == 0 {
+            Some(Side::Opponent)
+        } else {
+            None
+        };
+        if winner != self.winner {
+            return Err("The winner doesn't match the boards.");
+        }
+        let game = Self {
+            player_board,
+            opponent_board,
+            ..self
+        };
+        game.check_history()?;
+        Ok(game)
+    }
+
+    /// Fires the recorded shots again at fresh boards, checking they lead to this game.
+    /// Saves from before shots were recorded have nothing to check
+    fn check_history(&self) -> Result<(), &'static str> {
+        if self.history.is_empty() {
+            return Ok(());
+        }
+        let fresh = |board: &GameBoard| -> Result<GameBoard, &'static str> {
+            Ok(GameBoard::build(
+                rebuild_fleet(&self.rules, &board.ships)?,
+                &self.rules,
+            ))
+        };
+        let mut replayed = Game::new(
+            self.rules.clone(),
+            fresh(&self.player_board)?,
+            fresh(&self.opponent_board)?,
+        );
+        for volley in self.history.chunk_by(|a, b| a.turn == b.turn) {
+            let targets: Vec<Point> = volley.iter().map(|shot| shot.target.clone()).collect();
+            replayed.fire_recorded(&targets)?;
+        }
+        if replayed.history != self.history
+            || replayed.turn != self.turn
+            || replayed.player_board.board != self.player_board.board
+            || replayed.opponent_board.board != self.opponent_board.board
+        {
+            return Err("The shots don't match the game.");
+        }
+        Ok(())
+    }
+
+    /// Fires the current side's shot at the other side's fleet and passes the turn
+    pub fn fire(&mut self, target: Point) -> Result<ShotOutcome, &'static str> {
+        let mut outcomes = self.fire_volley(&[target])?;
+        Ok(outcomes.remove(0))
+    }
+
+    /// Fires a volley as it was recorded. A volley that sank the last ship may have been cut
+    /// short, so it is filled out with blank cells that are never reached
+    pub fn fire_recorded(&mut self, targets: &[Point]) -> Result<Vec<ShotOutcome>, &'static str> {
+        let board = &self.board(self.turn.other()).board;
+        let spare = (0..self.rules.height)
+            .flat_map(|y| (0..self.rules.width).map(move |x| Point(x, y)))
+            .filter(|point| {
+                board[point.1 as usize][point.0 as usize] == GridState::Blank
+                    && !targets.contains(point)
+            });
+        let missing = self.shots_per_turn().saturating_sub(targets.len());
+        let volley: Vec<Point> = targets.iter().cloned().chain(spare.take(missing)).collect();
+        let outcomes = self.fire_volley(&volley)?;
+        if outcomes.len() > targets.len() {
+            return Err("Wrong number of shots.");
+        }
+        if outcomes.len() < targets.len() {
+            return Err("Shots were fired after the last ship sank.");
+        }
+        Ok(outcomes)
+    }
+
+    /// Fires all of the current side's shots for the turn and passes the turn.
+    /// Every target is checked before any is fired, and the volley stops early if it
+    /// sinks the last ship, so there may be fewer outcomes than targets. When ships may
+    /// not touch, the water around the ships sunk is marked once the whole volley has landed
+    pub fn fire_volley(&mut self, targets: &[Point]) -> Result<Vec<ShotOutcome>, &'static str> {
+        if self.is_game_over() {
+            return Err("The game is over.");
+        }
+        if targets.len() != self.shots_per_turn() {
+            return Err("Wrong number of shots.");
+        }
+        let shooter = self.turn;
+        check_volley(&self.board(shooter.other()).board, targets)?;
+
+        let turn = self.history.last().map_or(1, |shot| shot.turn + 1);
+        let mut outcomes = vec![];
+        for target in targets {
+            let outcome = self.board_mut(shooter.other()).strike(target)?;
+            let destroyed = matches!(outcome, ShotOutcome::FleetDestroyed { .. });
+            self.history.push(ShotRecord {
+                turn,
+                shooter,
+                target: target.clone(),
+                outcome: outcome.clone(),
+            });
+            outcomes.push(outcome);
+            if destroyed {
+                self.winner = Some(shooter);
+                break;
+            }
+        }
+        if self.rules.no_touching {
+            let board = self.board_mut(shooter.other());
+            for (target, outcome) in targets.iter().zip(&outcomes) {
+                if outcome.is_sunk() {
+                    board.mark_around_sunk(target);
+                }
+            }
+        }
+        if self.winner.is_none() {
+            self.turn = shooter.other();
+        }
+        Ok(outcomes)
+    }
+}
+
+/// Shots a side fires in a turn: one, or in a salvo game one for each of its `ships_afloat`,
+/// but never more than there are cells left to fire at on the `target` board
+pub fn shots_per_turn(rules: &Rules, ships_afloat: u8, target: &[Vec<GridState>]) -> usize {
+    if !rules.salvo {
+        return 1;
+    }
+    let open_cells = target
+        .iter()
+        .flatten()
+        .filter(|state| **state == GridState::Blank)
+        .count();
+    (ships_afloat as usize).min(open_cells)
+}
+
+/// Checks every target of a volley lies on the board, and that none has already been
+/// fired at, in this volley or before
+pub fn check_volley(target: &[Vec<GridState>], targets: &[Point]) -> Result<(), &'static str> {
+    for (index, point) in targets.iter().enumerate() {
+        let Some(state) = target
+            .get(point.1 as usize)
+            .and_then(|row| row.get(point.0 as usize))
+        else {
+            return Err("Out of bounds.");
+        };
+        if *state != GridState::Blank || targets[..index].contains(point) {
+            return Err("Duplicate strike.");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::Rules;
+    use crate::ship::{Ship, ShipDirection, ShipKind};
+
+    fn destroyer() -> ShipKind {
+        ShipKind::build("Destroyer", 2).unwrap()
+    }
+
+    fn board_with_destroyer() -> GameBoard {
+        let rules = Rules::default();
+        GameBoard::build(
+            vec![Ship::build(destroyer(), 0, 0, ShipDirection::Up, &rules).unwrap()],
+            &rules,
+        )
+    }
+
+    #[test]
+    fn turns_alternate_until_a_fleet_is_sunk() {
+        let mut game = Game::new(
+            Rules::default(),
+            board_with_destroyer(),
+            board_with_destroyer(),
+        );
+        assert_eq!(game.turn(), Side::Player);
+        assert_eq!(
+            game.fire(Point(0, 0)),
+            Ok(ShotOutcome::Hit { kind: destroyer() })
+        );
+        assert_eq!(game.turn(), Side::Opponent);
+        assert_eq!(game.fire(Point(5, 5)), Ok(ShotOutcome::Miss));
+        assert_eq!(game.turn(), Side::Player);
+
+        // A rejected shot does not pass the turn
+        assert_eq!(game.fire(Point(0, 0)), Err("Duplicate strike."));
+        assert_eq!(game.turn(), Side::Player);
+
+        assert_eq!(
+            game.fire(Point(0, 1)),
+            Ok(ShotOutcome::FleetDestroyed { kind: destroyer() })
+        );
+        assert!(game.is_game_over());
+        assert_eq!(game.winner(), Some(Side::Player));
+        assert_eq!(game.fire(Point(1, 1)), Err("The game is over."));
+    }
+
+    #[test]
+    fn salvos_fire_one_shot_per_ship_afloat() {
+        let rules = Rules {
+            salvo: true,
+            ..Rules::default()
+        };
+        let two_destroyers = || {
+            GameBoard::build(
+                vec![
+                    Ship::build(destroyer(), 0, 0, ShipDirection::Up, &rules).unwrap(),
+                    Ship::build(destroyer(), 5, 0, ShipDirection::Up, &rules).unwrap(),
+                ],
+                &rules,
+            )
+        };
+        let mut game = Game::new(rules.clone(), two_destroyers(), two_destroyers());
+        assert_eq!(game.shots_per_turn(), 2);
+        assert_eq!(game.fire(Point(0, 0)), Err("Wrong number of shots."));
+        assert_eq!(
+            game.fire_volley(&[Point(0, 0), Point(0, 0)]),
+            Err("Duplicate strike.")
+        );
+        assert_eq!(
+            game.fire_volley(&[Point(0, 0), Point(0, 1)]),
+            Ok(vec![
+                ShotOutcome::Hit { kind: destroyer() },
+                ShotOutcome::Sunk { kind: destroyer() }
+            ])
+        );
+
+        // The opponent lost a ship, so it fires only once
+        assert_eq!(game.turn(), Side::Opponent);
+        assert_eq!(game.shots_per_turn(), 1);
+        assert_eq!(game.fire(Point(9, 9)), Ok(ShotOutcome::Miss));
+
+        // The volley stops once the last ship sinks
+        assert_eq!(
+            game.fire_volley(&[Point(5, 0), Point(5, 1)]),
+            Ok(vec![
+                ShotOutcome::Hit { kind: destroyer() },
+                ShotOutcome::FleetDestroyed { kind: destroyer() }
+            ])
+        );
+        assert_eq!(game.winner(), Some(Side::Player));
+
+        // Every shot is recorded, with the shots of a salvo sharing a turn
+        let turns: Vec<(u32, Side)> = game
+            .history()
+            .iter()
+            .map(|shot| (shot.turn, shot.shooter))
+            .collect();
+        assert_eq!(
+            turns,
+            [
+                (1, Side::Player),
+                (1, Side::Player),
+                (2, Side::Opponent),
+                (3, Side::Player),
+                (3, Side::Player)
+            ]
+        );
+        assert_eq!(game.history()[2].target, Point(9, 9));
+        assert_eq!(game.history()[2].outcome, ShotOutcome::Miss);
+    }
+
+    #[test]
+    fn water_around_sunk_ships_is_marked_when_ships_may_not_touch() {
+        let rules = Rules {
+            no_touching: true,
+            ..Rules::default()
+        };
+        let board = || {
+            GameBoard::build(
+                vec![
+                    Ship::build(destroyer(), 0, 0, ShipDirection::Up, &rules).unwrap(),
+                    Ship::build(destroyer(), 5, 5, ShipDirection::Left, &rules).unwrap(),
+                ],
+                &rules,
+            )
+        };
+        let mut game = Game::new(rules.clone(), board(), board());
+        game.fire(Point(5, 5)).unwrap();
+        game.fire(Point(9, 9)).unwrap();
+        // A hit alone marks nothing
+        assert_eq!(game.opponent_board.board[4][4], GridState::Blank);
+        game.fire(Point(6, 5)).unwrap();
+
+        let marked = |board: &GameBoard| {
+            board
+                .board
+                .iter()
+                .flatten()
+                .filter(|cell| **cell == GridState::Miss)
+                .count()
+        };
+        // The ten cells around the sunk destroyer, and no others
+        assert_eq!(marked(&game.opponent_board), 10);
+        assert_eq!(game.opponent_board.board[4][4], GridState::Miss);
+        assert_eq!(game.opponent_board.board[5][7], GridState::Miss);
+        assert_eq!(game.opponent_board.board[0][0], GridState::Blank);
+        game.fire(Point(9, 8)).unwrap();
+        assert_eq!(game.fire(Point(4, 5)), Err("Duplicate strike."));
+        assert_eq!(game.history().len(), 4);
+        assert_eq!(game.opponent_board.view().board, game.opponent_board.board);
+
+        // The view of the shots alone deduces the same cells
+        let mut view = crate::view::BoardView::new(10, 10, rules.fleet.clone());
+        for shot in game
+            .history()
+            .iter()
+            .filter(|shot| shot.shooter == Side::Player)
+        {
+            view.record(&shot.target, &shot.outcome);
+        }
+        view.mark_around_sunk();
+        assert_eq!(view.board, game.opponent_board.board);
+    }
+}
@@ -0,0 +1,206 @@
+//! Checking the other player of an online game told the truth about every shot.
+//!
+//! Before the first shot each side sends a commitment: a SHA-256 hash of its fleet layout
+//! and a random salt. Once the game is over each side reveals the layout and salt, and the
+//! other side checks the hash, then replays every shot it fired against the layout.
+//! The salt stops the layout being found by hashing every possible fleet.
+
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use super::board::{GameBoard, ShotOutcome, rebuild_fleet};
+use super::rules::Rules;
+use super::ship::{Point, Ship};
+
+/// A fleet layout, written out so it can be hashed now and revealed later
+#[derive(Debug, Clone, PartialEq)]
+pub struct SealedFleet {
+    /// Random hex digits, unique to the game
+    pub salt: String,
+    /// The ships as JSON, exactly as hashed
+    pub fleet: String,
+}
+
+impl SealedFleet {
+    /// Seals a fleet with a fresh random salt
+    pub fn seal(ships: &[Ship]) -> Self {
+        let salt: [u8; 16] = rand::rng().random();
+        Self {
+            salt: to_hex(&salt),
+            fleet: serde_json::to_string(ships).expect("Ships can always be serialised"),
+        }
+    }
+
+    /// The hash that commits to the fleet without giving it away
+    pub fn commitment(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_bytes());
+        hasher.update(b":");
+        hasher.update(self.fleet.as_bytes());
+        to_hex(&hasher.finalize())
+    }
+}
+
+/// The result of checking a revealed fleet
+#[derive(Debug, Clone)]
+pub enum Verdict {
+    /// Every reported outcome matched. Holds the revealed board with the shots replayed on it
+    Fair(GameBoard),
+    /// The other side lied, for the given reason
+    Cheated(&'static str),
+}
+
+/// Checks a revealed fleet against its commitment and the rules, then replays the shots
+/// fired at it, in order, to check each reported outcome
+pub fn verify(
+    rules: &Rules,
+    revealed: &SealedFleet,
+    commitment: &str,
+    shots: &[(Point, ShotOutcome)],
+) -> Verdict {
+    if revealed.commitment() != commitment {
+        return Verdict::Cheated("The revealed fleet does not match the one committed to.");
+    }
+    let Ok(mut board) = rebuild(rules, &revealed.fleet) else {
+        return Verdict::Cheated("The revealed fleet breaks the rules.");
+    };
+    for (target, reported) in shots {
+        if board.strike(target).as_ref() != Ok(reported) {
+            return Verdict::Cheated("A reported shot does not match the revealed fleet.");
+        }
+    }
+    Verdict::Fair(board)
+}
+
+/// Checks the replies to the shots fired so far could all be true of some fleet of the
+/// rules, so a side that lies and never lets its fleet be destroyed is still caught
+pub fn check_replies(rules: &Rules, shots: &[(Point, ShotOutcome)]) -> Result<(), &'static str> {
+    let mut hits = 0;
+    for (index, kind) in rules.fleet.iter().enumerate() {
+        // Ships of the same kind are counted together, at the first of them
+        if rules.fleet[..index].contains(kind) {
+            continue;
+        }
+        let ships = rules.fleet.iter().filter(|other| *other == kind).count();
+        let replies: Vec<&ShotOutcome> = shots
+            .iter()
+            .map(|(_, outcome)| outcome)
+            .filter(|outcome| outcome.kind() == Some(kind))
+            .collect();
+        if replies.len() > ships * kind.get_len() as usize
+            || replies.iter().filter(|outcome| outcome.is_sunk()).count() > ships
+        {
+            return Err("More hits were reported on a ship than it could take.");
+        }
+        hits += replies.len();
+    }
+    if shots.iter().filter(|(_, outcome)| outcome.is_hit()).count() != hits {
+        return Err("A ship that isn't in the fleet was reported hit.");
+    }
+    // Every part of the fleet not yet hit must still lie somewhere not yet fired at
+    let fleet_cells: usize = rules.fleet.iter().map(|kind| kind.get_len() as usize).sum();
+    let open_cells = rules.width as usize * rules.height as usize - shots.len();
+    if fleet_cells - hits > open_cells {
+        return Err("The misses reported leave no room for the rest of the fleet.");
+    }
+    Ok(())
+}
+
+/// Places the revealed ships again from their positions, so nothing else they claim is trusted
+fn rebuild(rules: &Rules, fleet: &str) -> Result<GameBoard, &'static str> {
+    let revealed: Vec<Ship> = serde_json::from_str(fleet).map_err(|_| "Invalid fleet.")?;
+    Ok(GameBoard::build(rebuild_fleet(rules, &revealed)?, rules))
+}
+
+/// Writes bytes as lower case hex digits
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::generate_game_board;
+    use crate::ship::ShipKind;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn honest_fleets_pass_and_lies_are_caught() {
+        let rules = Rules::default();
+        let mut board = generate_game_board(&rules, &mut StdRng::seed_from_u64(4));
+        let sealed = SealedFleet::seal(&board.ships);
+        let commitment = sealed.commitment();
+        assert_eq!(commitment.len(), 64);
+        // The salt makes every seal of the same fleet different
+        assert_ne!(SealedFleet::seal(&board.ships).commitment(), commitment);
+
+        let hull = board.ships[0].points[0].clone();
+        let shots: Vec<(Point, ShotOutcome)> = [hull, Point(0, 0), Point(9, 9)]
+            .into_iter()
+            .filter_map(|target| Some((target.clone(), board.strike(&target).ok()?)))
+            .collect();
+        assert!(matches!(
+            verify(&rules, &sealed, &commitment, &shots),
+            Verdict::Fair(_)
+        ));
+
+        // Claiming a hit was a miss
+        let mut lies = shots.clone();
+        lies[0].1 = ShotOutcome::Miss;
+        assert!(matches!(
+            verify(&rules, &sealed, &commitment, &lies),
+            Verdict::Cheated("A reported shot does not match the revealed fleet.")
+        ));
+
+        // Revealing a different fleet to the one committed to
+        let mut moved = board.ships.clone();
+        moved.swap(0, 1);
+        let swapped = SealedFleet {
+            salt: sealed.salt.clone(),
+            fleet: serde_json::to_string(&moved).unwrap(),
+        };
+        assert!(matches!(
+            verify(&rules, &swapped, &commitment, &shots),
+            Verdict::Cheated("The revealed fleet does not match the one committed to.")
+        ));
+        // Committing to a fleet that breaks the rules
+        assert!(matches!(
+            verify(&rules, &swapped, &swapped.commitment(), &shots),
+            Verdict::Cheated("The revealed fleet breaks the rules.")
+        ));
+    }
+
+    #[test]
+    fn replies_that_cant_fit_the_fleet_are_caught() {
+        let rules = Rules::build(5, 5, vec![ShipKind::build("Destroyer", 2).unwrap()]).unwrap();
+        let destroyer = ShotOutcome::Hit {
+            kind: rules.fleet[0].clone(),
+        };
+        let cells = (0..25).map(|i| Point(i % 5, i / 5));
+        let misses: Vec<(Point, ShotOutcome)> =
+            cells.map(|target| (target, ShotOutcome::Miss)).collect();
+        // Two cells are left for the destroyer, then only one
+        assert_eq!(check_replies(&rules, &misses[..23]), Ok(()));
+        assert_eq!(
+            check_replies(&rules, &misses[..24]),
+            Err("The misses reported leave no room for the rest of the fleet.")
+        );
+
+        let mut hits = misses[..3].to_vec();
+        hits[0].1 = destroyer.clone();
+        hits[1].1 = destroyer.clone();
+        assert_eq!(check_replies(&rules, &hits), Ok(()));
+        hits[2].1 = destroyer;
+        assert_eq!(
+            check_replies(&rules, &hits),
+            Err("More hits were reported on a ship than it could take.")
+        );
+        hits[2].1 = ShotOutcome::Sunk {
+            kind: ShipKind::build("Raft", 1).unwrap(),
+        };
+        assert_eq!(
+            check_replies(&rules, &hits),
+            Err("A ship that isn't in the fleet was reported hit.")
+        );
+    }
+}
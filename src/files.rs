@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Folder holding the files the game keeps between runs
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("CL_BATTLESHIP_HOME") {
+        return PathBuf::from(dir);
+    }
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".cl-battleship"))
+        .unwrap_or_else(|| PathBuf::from(".cl-battleship"))
+}
+
+/// Where the game in progress is saved
+pub fn save_path() -> PathBuf {
+    data_dir().join("save.json")
+}
+
+/// Where the statistics of finished games are kept
+pub fn stats_path() -> PathBuf {
+    data_dir().join("stats.json")
+}
+
+/// Folder holding the replays of finished games
+pub fn replays_dir() -> PathBuf {
+    data_dir().join("replays")
+}
+
+/// Where to keep the replay of a game that just finished. Named by the time, so the
+/// names sort from oldest to newest
+pub fn new_replay_path() -> PathBuf {
+    replays_dir().join(format!("{}.json", timestamp()))
+}
+
+/// Folder holding the game records written out from the replays
+pub fn records_dir() -> PathBuf {
+    data_dir().join("records")
+}
+
+/// Where to write out the record of a game, named by the time like the replays
+pub fn new_record_path() -> PathBuf {
+    records_dir().join(format!("{}.txt", timestamp()))
+}
+
+/// The current time in milliseconds, padded so the names sort in order
+fn timestamp() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis());
+    format!("{:015}", millis)
+}
@@ -1,318 +1,942 @@
-use std::vec;
-
-use console::{Alignment, Style, Term, style};
-use rand::{Rng, rng};
+use cl_battleship::{
+    analysis::{self, Analysis, Rating},
+    board::{GameBoard, GridState, ShotOutcome},
+    bot::{self, Difficulty},
+    engine::{self, Side},
+    rules::Rules,
+    replay::Replay,
+    save::{self, SaveFile, SeededRng},
+    ship::Point,
+    stats,
+    view::BoardView,
+};
+use console::{Alignment, Key, Style, StyledObject, Term, style};
 
 use crate::{
-    create_board::{self, GameBoard, GridState},
-    ship::Point,
-    terminal_utils::{self, create_colored_grid},
+    create_board, files, menu,
+    mouse::{self, Button, Input},
+    replays,
+    terminal_utils::{self, create_colored_grid, grid_width, print_center},
 };
 
+/// What the player typed at the striking prompt
+pub enum TargetInput {
+    Fire(Vec<Point>),
+    Save,
+    Load,
+    /// Only offered online, where games can't be saved or loaded
+    Leave,
+}
+
+/// Who the player is up against
+pub enum Opponent {
+    /// The Admiral, playing at a difficulty
+    Admiral(Difficulty),
+    /// A second player sharing the keyboard. Holds the names of both players
+    HotSeat([String; 2]),
+}
+
+impl Opponent {
+    /// How a side is named in messages
+    pub fn name(&self, side: Side) -> String {
+        match (self, side) {
+            (Opponent::Admiral(_), Side::Player) => "You".to_string(),
+            (Opponent::Admiral(_), Side::Opponent) => "The Admiral".to_string(),
+            (Opponent::HotSeat(names), Side::Player) => names[0].clone(),
+            (Opponent::HotSeat(names), Side::Opponent) => names[1].clone(),
+        }
+    }
+
+    /// How the shots of a side are announced
+    pub fn announcer(&self, shooter: Side) -> Announcer {
+        let owner = match (self, shooter.other()) {
+            (Opponent::Admiral(_), Side::Player) => "your".to_string(),
+            (Opponent::Admiral(_), Side::Opponent) => "the".to_string(),
+            (Opponent::HotSeat(_), side) => format!("{}'s", self.name(side)),
+        };
+        Announcer {
+            name: self.name(shooter),
+            owner,
+            hostile: matches!(self, Opponent::Admiral(_)) && shooter == Side::Opponent,
+        }
+    }
+}
+
 pub struct Game {
-    player_board: GameBoard,
-    bot_board: GameBoard,
-    is_game_over: bool,
+    engine: engine::Game,
+    opponent: Opponent,
+    /// Drives the Admiral, so the same seed and the same shots replay the same game
+    rng: SeededRng,
+    /// The seed `rng` started from, unless the game was resumed from a save too old to
+    /// have kept it
+    seed: Option<u64>,
+    /// Turns on which the player asked for a hint
+    hints: u32,
 }
 
 impl Game {
     /// Create a game
-    pub fn new() -> Self {
+    pub fn new(rules: Rules, difficulty: Difficulty, seed: u64) -> Self {
+        let mut rng = SeededRng::new(seed);
+        let player_board = create_board::start(&rules);
+        let bot_board = difficulty.generate_game_board(&rules, &mut rng);
         Self {
-            player_board: create_board::start(),
-            bot_board: create_board::generate_game_board(),
-            is_game_over: false,
+            engine: engine::Game::new(rules, player_board, bot_board),
+            opponent: Opponent::Admiral(difficulty),
+            rng,
+            seed: Some(seed),
+            hints: 0,
         }
     }
+
+    /// Create a game between two players at the same keyboard, who take turns placing
+    /// their fleets
+    pub fn new_hot_seat(rules: Rules, names: [String; 2], seed: u64) -> Self {
+        let term = Term::buffered_stdout();
+        let [first_board, second_board] = names.clone().map(|name| {
+            hand_off(&term, &name, "place your fleet");
+            create_board::start(&rules)
+        });
+        Self {
+            engine: engine::Game::new(rules, first_board, second_board),
+            opponent: Opponent::HotSeat(names),
+            rng: SeededRng::new(seed),
+            seed: Some(seed),
+            hints: 0,
+        }
+    }
+
+    /// Resume the last saved game, drawing the Admiral's numbers from where it stopped
+    pub fn resume() -> Result<Self, &'static str> {
+        let save = save::load_game(&files::save_path())?;
+        let seed = save.rng.as_ref().map(SeededRng::seed);
+        Ok(Self {
+            engine: save.game,
+            opponent: match save.hot_seat {
+                Some(names) => Opponent::HotSeat(names),
+                None => Opponent::Admiral(save.difficulty),
+            },
+            rng: save.rng.unwrap_or_else(|| SeededRng::new(rand::random())),
+            seed,
+            hints: save.hints,
+        })
+    }
+
+    /// Create another game against the same opponent, under the same rules
+    pub fn rematch(&self, seed: u64) -> Self {
+        let rules = self.engine.rules().clone();
+        match &self.opponent {
+            Opponent::Admiral(difficulty) => Self::new(rules, *difficulty, seed),
+            Opponent::HotSeat(names) => Self::new_hot_seat(rules, names.clone(), seed),
+        }
+    }
+
+    /// Check weather there is a saved game to resume
+    pub fn can_resume() -> bool {
+        files::save_path().exists()
+    }
+
     /// Start the game
     pub fn start_game(&mut self) {
+        if let Opponent::HotSeat(_) = self.opponent {
+            return self.start_hot_seat();
+        }
         let term = Term::buffered_stdout();
+        mouse::anchor(&term);
         self.render(&term);
         term.flush();
+        let mut cursor = Point(0, 0);
         loop {
-            let target = get_target(&term, &self.bot_board);
-            let first_message = self.update_hit(false, target);
-            if self.is_game_over {
-                term.clear_last_lines(26);
+            let shots = self.engine.shots_per_turn();
+            let draw = |term: &Term, crosshair: &Crosshair| {
+                self.render_for(term, Side::Player, Some(crosshair))
+            };
+            let target_board = self.engine.opponent_board.view();
+            let mut hinted = false;
+            let input = get_target(
+                &term,
+                &target_board,
+                shots,
+                &mut cursor,
+                &mut hinted,
+                false,
+                &draw,
+            );
+            if hinted {
+                self.hints += 1;
+            }
+            let targets = match input {
+                TargetInput::Fire(targets) => targets,
+                TargetInput::Save => {
+                    let message = match self.save() {
+                        Ok(()) => format!("{} Game saved.", style(" Save ").on_blue().bold()),
+                        Err(error) => format!("{} {}", style(" Error ").on_red().bold(), error),
+                    };
+                    term.clear_last_lines(self.screen_lines());
+                    term.write_line(&message);
+                    term.write_line("");
+                    self.render(&term);
+                    continue;
+                }
+                TargetInput::Load => {
+                    // Clear using the current board, since the saved one may be a different size
+                    term.clear_last_lines(self.screen_lines());
+                    let message = match Self::resume() {
+                        Ok(game) => {
+                            *self = game;
+                            if let Opponent::HotSeat(_) = self.opponent {
+                                return self.start_hot_seat();
+                            }
+                            format!("{} Game loaded.", style(" Load ").on_blue().bold())
+                        }
+                        Err(error) => format!("{} {}", style(" Error ").on_red().bold(), error),
+                    };
+                    term.write_line(&message);
+                    term.write_line("");
+                    self.render(&term);
+                    continue;
+                }
+                TargetInput::Leave => unreachable!("Only online games offer to leave"),
+            };
+            let first_message = self.update_hit(targets);
+            if self.engine.is_game_over() {
+                std::fs::remove_file(files::save_path()).ok();
+                term.clear_last_lines(self.screen_lines());
                 term.write_line(&first_message);
-                term.write_line(&format!("{} You won!", style("  Win ").on_yellow().bold(),));
+                term.write_line(&format!(
+                    "{} You won!{}",
+                    style("  Win ").on_yellow().bold(),
+                    self.seed_note()
+                ));
                 self.render(&term);
                 term.flush();
                 break;
             }
-            let bot_target = gen_bot_target(&self.player_board);
-            let second_message = self.update_hit(true, bot_target);
-            if self.is_game_over {
-                term.clear_last_lines(26);
+            let Opponent::Admiral(difficulty) = self.opponent else {
+                unreachable!("Hot-seat games are played by start_hot_seat")
+            };
+            let bot_targets = difficulty.gen_volley(
+                &self.engine.player_board.view(),
+                self.engine.shots_per_turn(),
+                &mut self.rng,
+            );
+            let second_message = self.update_hit(bot_targets);
+            if self.engine.is_game_over() {
+                std::fs::remove_file(files::save_path()).ok();
+                term.clear_last_lines(self.screen_lines());
                 term.write_line(&first_message);
                 term.write_line(&second_message);
-                term.write_line(&format!("{} You lose!", style(" Loss ").on_black().bold(),));
+                term.write_line(&format!(
+                    "{} You lose!{}",
+                    style(" Loss ").on_black().bold(),
+                    self.seed_note()
+                ));
                 self.render(&term);
                 term.flush();
                 break;
             }
-            term.clear_last_lines(26);
+            // Keep the battle safe in case the terminal closes
+            self.save().ok();
+            term.clear_last_lines(self.screen_lines());
             term.write_line(&first_message);
             term.write_line(&second_message);
             self.render(&term);
             term.flush();
         }
+        self.record_stats();
+        self.keep_replay();
+    }
+
+    /// Keeps the finished game so it can be watched again
+    fn keep_replay(&self) {
+        let (difficulty, hot_seat) = match &self.opponent {
+            Opponent::Admiral(difficulty) => (*difficulty, None),
+            Opponent::HotSeat(names) => (Difficulty::default(), Some(names.clone())),
+        };
+        let mut replay = Replay::new(&self.engine, difficulty, hot_seat);
+        replay.seed = self.seed;
+        replays::keep(&replay);
+    }
+
+    /// Shows how well the shots of the game were chosen. Both players' shots are rated in a
+    /// hot-seat game
+    pub fn review(&self) {
+        let term = Term::buffered_stdout();
+        let sides = match &self.opponent {
+            Opponent::Admiral(_) => vec![Side::Player],
+            Opponent::HotSeat(_) => vec![Side::Player, Side::Opponent],
+        };
+        let lines: Vec<String> = sides
+            .into_iter()
+            .flat_map(|side| {
+                let analysis = analysis::analyse(self.engine.rules(), self.engine.history(), side);
+                describe_analysis(&self.opponent.name(side), &analysis)
+            })
+            .collect();
+        menu::show(&term, &lines);
+    }
+
+    /// Adds the finished game to the statistics
+    fn record_stats(&self) {
+        let path = files::stats_path();
+        if let Ok(mut stats) = stats::load_stats(&path) {
+            stats.record(&self.engine);
+            stats.hints += self.hints;
+            stats::save_stats(&path, &stats).ok();
+        }
+    }
+
+    /// Plays a game between two players at the same keyboard. The screen is hidden
+    /// between turns so neither player sees the other's fleet
+    fn start_hot_seat(&mut self) {
+        let term = Term::buffered_stdout();
+        // What the last player did, shown to the next one
+        let mut last_message: Option<String> = None;
+        // Each player keeps their own crosshair
+        let mut cursors = [Point(0, 0), Point(0, 0)];
+        loop {
+            let shooter = self.engine.turn();
+            hand_off(&term, &self.opponent.name(shooter), "take your turn");
+            mouse::anchor(&term);
+            if let Some(message) = &last_message {
+                term.write_line(message);
+            }
+            self.render_for(&term, shooter, None);
+            let shots = self.engine.shots_per_turn();
+            let cursor = &mut cursors[if shooter == Side::Player { 0 } else { 1 }];
+            let targets = loop {
+                let draw = |term: &Term, crosshair: &Crosshair| {
+                    self.render_for(term, shooter, Some(crosshair))
+                };
+                let target_board = self.engine.board(shooter.other()).view();
+                // Hints only count towards the statistics of games against the Admiral
+                let input = get_target(
+                    &term,
+                    &target_board,
+                    shots,
+                    cursor,
+                    &mut false,
+                    false,
+                    &draw,
+                );
+                let message = match input {
+                    TargetInput::Fire(targets) => break Some(targets),
+                    TargetInput::Save => match self.save() {
+                        Ok(()) => format!("{} Game saved.", style(" Save ").on_blue().bold()),
+                        Err(error) => format!("{} {}", style(" Error ").on_red().bold(), error),
+                    },
+                    TargetInput::Load => match Self::resume() {
+                        Ok(game) => {
+                            *self = game;
+                            break None;
+                        }
+                        Err(error) => format!("{} {}", style(" Error ").on_red().bold(), error),
+                    },
+                    TargetInput::Leave => unreachable!("Only online games offer to leave"),
+                };
+                term.clear_last_lines(self.screen_lines());
+                term.write_line(&message);
+                self.render_for(&term, shooter, None);
+            };
+            let Some(targets) = targets else {
+                // A loaded game may be against the Admiral, so start it afresh
+                term.clear_screen().ok();
+                return self.start_game();
+            };
+
+            let message = self.update_hit(targets);
+            term.clear_screen().ok();
+            term.write_line(&message);
+            if self.engine.is_game_over() {
+                std::fs::remove_file(files::save_path()).ok();
+                term.write_line(&format!(
+                    "{} {} won!",
+                    style("  Win ").on_yellow().bold(),
+                    self.opponent.name(shooter)
+                ));
+                self.render_for(&term, shooter, None);
+                term.flush();
+                self.keep_replay();
+                break;
+            }
+            // Keep the battle safe in case the terminal closes
+            self.save().ok();
+            self.render_for(&term, shooter, None);
+            print_center(
+                &term,
+                &format!("- Press {} to end your turn -", style("Enter").blue().bold()),
+            );
+            term.flush();
+            wait_for_enter(&term);
+            last_message = Some(message);
+        }
+    }
+
+    /// Writes the game and where the Admiral's numbers are up to into the save file
+    fn save(&self) -> Result<(), &'static str> {
+        let mut save = match &self.opponent {
+            Opponent::Admiral(difficulty) => SaveFile::new(self.engine.clone(), *difficulty),
+            Opponent::HotSeat(names) => {
+                let mut save = SaveFile::new(self.engine.clone(), Difficulty::default());
+                save.hot_seat = Some(names.clone());
+                save
+            }
+        };
+        save.hints = self.hints;
+        save.rng = Some(self.rng.clone());
+        save::save_game(&files::save_path(), &save)
+    }
+
+    /// The seed, for the message at the end of a game, when it is known
+    fn seed_note(&self) -> String {
+        self.seed
+            .map_or(String::new(), |seed| format!(" (Seed {})", seed))
+    }
+
+    /// Lines taken up by the grids and messages
+    fn screen_lines(&self) -> usize {
+        2 * self.engine.rules().height as usize + 6
     }
 
     /// Render grids and messages
     fn render(&self, term: &Term) {
-        let player_grid = generate_grid(&self.player_board, true);
-        let bot_grid = generate_grid(&self.bot_board, false || self.is_game_over);
-        let grids = terminal_utils::join(bot_grid, player_grid, 2);
-        let grid_width = 46;
-        let grid_labels = format!(
-            "\n{}{}",
-            style(
-                console::pad_str("Target Board", grid_width, Alignment::Center, None,).to_string()
-            )
-            .bold(),
-            style(
-                console::pad_str("Your Board", grid_width - 1, Alignment::Center, None,)
-                    .to_string()
-            )
-            .bold()
+        self.render_for(term, Side::Player, None);
+    }
+
+    /// Render grids as seen by one side: its own fleet, and the board it fires at with
+    /// the crosshair, if it is aiming
+    fn render_for(&self, term: &Term, side: Side, crosshair: Option<&Crosshair>) {
+        let own_grid = generate_grid(self.engine.board(side), true, None);
+        let target_grid = generate_grid(
+            self.engine.board(side.other()),
+            self.engine.is_game_over(),
+            crosshair,
         );
-        term.write_line(&grid_labels);
-        term.write_line(&grids);
-        term.flush();
+        render_grids(term, self.engine.rules(), target_grid, own_grid);
     }
 
-    /// Updates information about the hit
-    fn update_hit(&mut self, is_player_board_hit: bool, target: Point) -> String {
-        let hit_board = if is_player_board_hit {
-            &mut self.player_board
+    /// Fires the turn's shots and describes what happened
+    fn update_hit(&mut self, targets: Vec<Point>) -> String {
+        let shooter = self.engine.turn();
+        let outcomes = self
+            .engine
+            .fire_volley(&targets)
+            .expect("Targets should have been validated");
+        self.opponent.announcer(shooter).describe_turn(&targets, &outcomes)
+    }
+}
+
+/// How the shots of one side are described in the message area
+pub struct Announcer {
+    /// Who fired, such as "You" or "The Admiral"
+    pub name: String,
+    /// Whose fleet was fired at, as in "hit your Cruiser"
+    pub owner: String,
+    /// Weather a hit is bad news for whoever is watching
+    pub hostile: bool,
+}
+
+impl Announcer {
+    /// Describes a turn of one shot or a whole salvo
+    pub fn describe_turn(&self, targets: &[Point], outcomes: &[ShotOutcome]) -> String {
+        if let [outcome] = outcomes {
+            self.describe_outcome(outcome)
         } else {
-            &mut self.bot_board
+            self.describe_volley(targets, outcomes)
+        }
+    }
+
+    /// Formats the result of a shot for the message area
+    fn describe_outcome(&self, outcome: &ShotOutcome) -> String {
+        let Some(kind) = outcome.kind() else {
+            return format!("{} {} missed.", style(" Miss ").on_white().bold(), self.name);
         };
-        let hit_index = hit_board
-            .ships
+        let mut hit_message = format!(
+            "{} {} hit {} {}.",
+            self.label("  Hit "),
+            self.name,
+            self.owner,
+            kind.get_name()
+        );
+        if let ShotOutcome::Sunk { .. } | ShotOutcome::FleetDestroyed { .. } = outcome {
+            hit_message += &format!(
+                "\n{} {} sunk {} {}.",
+                self.label(" Sunk "),
+                self.name,
+                self.owner,
+                kind.get_name()
+            );
+        }
+        hit_message
+    }
+
+    /// Summarises a salvo for the message area: how many shots hit, and any ships sunk
+    fn describe_volley(&self, targets: &[Point], outcomes: &[ShotOutcome]) -> String {
+        let hits: Vec<String> = targets
             .iter()
-            .position(|ship| ship.is_hit_by(&target));
-        if let Some(hit_index) = hit_index {
-            let hit_ship = &mut hit_board.ships[hit_index];
-            hit_board.board[target.1 as usize][target.0 as usize] = GridState::Hit;
-            let mut hit_message = String::new();
-            if is_player_board_hit {
-                hit_message += &format!(
-                    "{} The Admiral hit your {}.",
-                    style("  Hit ").on_red().bold(),
-                    hit_ship.kind.get_name()
-                )
+            .zip(outcomes)
+            .filter(|(_, outcome)| outcome.is_hit())
+            .map(|(target, _)| target.to_string())
+            .collect();
+        let misses = outcomes.len() - hits.len();
+        let mut summary = format!(
+            "{} {} fired {} shots: {}, {}.",
+            if hits.is_empty() {
+                style("Salvo ").on_white().bold()
             } else {
-                hit_message += &format!(
-                    "{} You hit the {}.",
-                    style("  Hit ").on_green().bold(),
-                    hit_ship.kind.get_name()
-                )
-            };
-            hit_ship.hit(target);
-            if hit_ship.is_sunk() {
-                if is_player_board_hit {
-                    hit_message += &format!(
-                        "\n{} The Admiral sunk your {}.",
-                        style(" Sunk ").on_red().bold(),
-                        hit_ship.kind.get_name()
-                    )
-                } else {
-                    hit_message += &format!(
-                        "\n{} You sunk the {}.",
-                        style(" Sunk ").on_green().bold(),
-                        hit_ship.kind.get_name()
-                    )
-                };
-                hit_board.ships_left -= 1;
-                if hit_board.ships_left == 0 {
-                    self.is_game_over = true;
-                };
+                self.label("Salvo ")
+            },
+            self.name,
+            outcomes.len(),
+            plural(hits.len(), "hit", "hits"),
+            plural(misses, "miss", "misses")
+        );
+        if !hits.is_empty() {
+            summary = format!("{} Hits at {}.", summary, hits.join(", "));
+        }
+        for outcome in outcomes {
+            if let ShotOutcome::Sunk { kind } | ShotOutcome::FleetDestroyed { kind } = outcome {
+                summary += &format!(
+                    "\n{} {} sunk {} {}.",
+                    self.label(" Sunk "),
+                    self.name,
+                    self.owner,
+                    kind.get_name()
+                );
             }
-            hit_message
+        }
+        summary
+    }
+
+    /// A message label, red if the news is bad and green if it is good
+    fn label(&self, text: &'static str) -> StyledObject<&'static str> {
+        if self.hostile {
+            style(text).on_red().bold()
         } else {
-            hit_board.board[target.1 as usize][target.0 as usize] = GridState::Miss;
-            if is_player_board_hit {
-                format!("{} The Admiral missed.", style(" Miss ").on_white().bold())
-            } else {
-                format!("{} You missed.", style(" Miss ").on_white().bold())
-            }
+            style(text).on_green().bold()
         }
     }
 }
 
-/// Read user input to determine their target
-fn get_target(term: &Term, target_board: &GameBoard) -> Point {
-    term.write_line("Enter the striking coordinates: ");
-    'valid_input: loop {
-        term.flush();
-        let input = term.read_line();
-        if input.is_err() {
-            continue;
+/// A side's rated shots as lines of text, with the worst few spelled out
+fn describe_analysis(name: &str, analysis: &Analysis) -> Vec<String> {
+    let Some(accuracy) = analysis.accuracy() else {
+        return vec![format!("{}: no shots to review.", style(name).bold())];
+    };
+    let counts: Vec<String> = Rating::ALL
+        .iter()
+        .map(|rating| format!("{}: {}", rating.get_name(), analysis.count(*rating)))
+        .collect();
+    let mut lines = vec![format!(
+        "{}: {:.0}% accuracy   {}",
+        style(name).bold(),
+        accuracy,
+        counts.join("   ")
+    )];
+    lines.extend(analysis.worst(3).iter().map(|review| {
+        format!(
+            "Turn {}: {} ({}), {} was the best shot",
+            review.turn,
+            review.target,
+            review.rating.get_name(),
+            review.best
+        )
+    }));
+    lines
+}
+
+/// Writes the target grid and the player's own grid side by side, with their titles
+pub fn render_grids(term: &Term, rules: &Rules, target_grid: String, own_grid: String) {
+    let grids = terminal_utils::join(target_grid, own_grid, 2);
+    let grid_width = grid_width(rules.width, rules.height) + 2;
+    let grid_labels = format!(
+        "\n{}{}",
+        style(
+            console::pad_str("Target Board", grid_width, Alignment::Center, None,).to_string()
+        )
+        .bold(),
+        style(
+            console::pad_str("Your Board", grid_width - 1, Alignment::Center, None,)
+                .to_string()
+        )
+        .bold()
+    );
+    term.write_line(&grid_labels);
+    term.write_line(&grids);
+    term.flush();
+}
+
+/// Hides the screen until the named player is at the keyboard
+fn hand_off(term: &Term, name: &str, task: &str) {
+    term.clear_screen().ok();
+    print_center(
+        term,
+        &format!(
+            "\n\nPass the keyboard to {}, then press {} to {}.",
+            style(name).bold(),
+            style("Enter").blue().bold(),
+            task
+        ),
+    );
+    term.flush();
+    wait_for_enter(term);
+    term.clear_screen().ok();
+    term.flush();
+}
+
+/// Blocks until the Enter key is pressed
+fn wait_for_enter(term: &Term) {
+    loop {
+        match term.read_key() {
+            Ok(Key::Enter) | Err(_) => break,
+            _ => (),
         }
-        let input = input.unwrap();
-        let mut char_pos: Option<char> = None;
-        let mut number_pos: Option<u8> = None;
-        for char in input.chars() {
-            match char {
-                'A'..='J' | 'a'..='j' => {
-                    if char_pos.is_some() {
-                        term.clear_last_lines(2);
-                        term.write_line(&format!("{} Multiple Letter Coordinates Provided. Enter new striking coordinates: ", style(" Error ").on_red().bold()));
-                        term.flush();
-                        continue 'valid_input;
-                    } else {
-                        char_pos = Some(char);
+    }
+}
+
+/// A count followed by the singular or plural noun
+fn plural(count: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
+/// Where the player is aiming on the Target Board
+pub struct Crosshair {
+    pub cursor: Point,
+    /// Cells picked for the current salvo
+    pub marked: Vec<Point>,
+    /// Shown over the blank cells once the player asks for a hint
+    pub hint: Option<Hint>,
+}
+
+/// Colours of the heatmap, from the least to the most likely cells to hold a ship
+const HEAT: [u8; 6] = [17, 19, 26, 32, 38, 51];
+
+/// Where the enemy ships are most likely to be, from the placements of those still afloat
+pub struct Hint {
+    /// How many placements could cover each cell
+    pub density: Vec<Vec<u32>>,
+    /// The cell recommended to fire at
+    pub best: Point,
+}
+
+impl Hint {
+    /// The colour of a blank cell. Cells no ship can cover are dark grey
+    fn style(&self, point: &Point) -> Style {
+        let most = self.density.iter().flatten().max().copied().unwrap_or(0);
+        let weight = self.density[point.1 as usize][point.0 as usize];
+        if weight == 0 {
+            return Style::new().color256(236);
+        }
+        let level = weight as usize * (HEAT.len() - 1) / most as usize;
+        Style::new().color256(HEAT[level])
+    }
+}
+
+/// Lets the player aim a crosshair at the Target Board with the arrow keys or WASD, and
+/// pick each target with Enter or Space. `draw` renders the grids with the crosshair, and
+/// `cursor` keeps its position between turns. Tab switches to typing coordinates, which
+/// is also used when keys can't be read. H shows a heatmap of where the ships are likely
+/// to be, and sets `hinted`. Typing offers to save or load the game, or to leave it when
+/// `online`
+pub fn get_target(
+    term: &Term,
+    target: &BoardView,
+    shots: usize,
+    cursor: &mut Point,
+    hinted: &mut bool,
+    online: bool,
+    draw: &dyn Fn(&Term, &Crosshair),
+) -> TargetInput {
+    let target_board = &target.board;
+    let grid_lines = 2 * target_board.len() + 6;
+    let mut marked: Vec<Point> = vec![];
+    let mut notice = None;
+    // The heatmap, once the player has asked for it
+    let mut density: Option<Vec<Vec<u32>>> = None;
+    let mut show_hint = false;
+    loop {
+        term.clear_last_lines(grid_lines);
+        let hint = match &density {
+            Some(density) if show_hint => {
+                let taken: Vec<&Point> = marked.iter().collect();
+                analysis::best_cell(target_board, density, &taken).map(|best| Hint {
+                    density: density.clone(),
+                    best,
+                })
+            }
+            _ => None,
+        };
+        let best = hint.as_ref().map(|hint| hint.best.clone());
+        draw(
+            term,
+            &Crosshair {
+                cursor: cursor.clone(),
+                marked: marked.clone(),
+                hint,
+            },
+        );
+        let help = format!(
+            "{}, WASD or mouse to aim, {} or {} to {}, {} to type, {} for a hint",
+            style("Arrows").bold(),
+            style("Enter").bold(),
+            style("Space").bold(),
+            if shots == 1 { "fire" } else { "mark" },
+            style("Tab").bold(),
+            style("H").bold()
+        );
+        let mut status = vec![];
+        if let Some(error) = notice.take() {
+            status.push(format!("{} {}", style(" Error ").on_red().bold(), error));
+        } else {
+            if shots > 1 {
+                status.push(format!("{} of {} marked", marked.len(), shots));
+            }
+            if let Some(best) = best {
+                status.push(format!(
+                    "{} Try {}",
+                    style(" Hint ")
+                        .on_color256(HEAT[HEAT.len() - 1])
+                        .black()
+                        .bold(),
+                    style(best).color256(208).bold()
+                ));
+            }
+        }
+        // The help and status each get a line short enough not to wrap on an 80 column
+        // terminal, since only whole lines are cleared
+        term.write_line(&help);
+        term.write_line(&status.join("  "));
+        term.flush();
+
+        let key = match mouse::read_input(term) {
+            // Clicking a cell aims at it and fires, like pressing Enter
+            Ok(Input::Click {
+                button: Button::Left,
+                column,
+                row,
+            }) => {
+                let size = (target_board[0].len() as u8, target_board.len() as u8);
+                match mouse::grid_cell(term, (column, row), grid_lines, 0, size) {
+                    Some(cell) => {
+                        *cursor = cell;
+                        Ok(Key::Enter)
                     }
+                    None => Ok(Key::Unknown),
                 }
-                '0'..='9' => {
-                    if let Some(digit) = number_pos {
-                        if digit == 1 && char == '0' {
-                            number_pos = Some(10);
-                        } else {
-                            term.clear_last_lines(2);
-                            term.write_line(&format!("{} Multiple Numeric Coordinates Provided. Enter new striking coordinates: ", style(" Error ").on_red().bold()));
-                            term.flush();
-                            continue 'valid_input;
-                        }
-                    } else if char != '0' {
-                        number_pos = Some(char as u8 - b'0');
-                    } else {
-                        term.clear_last_lines(2);
-                        term.write_line(&format!("{} Multiple Numeric Coordinates Provided. Enter new striking coordinates: ", style(" Error ").on_red().bold()));
-                        term.flush();
-                        continue 'valid_input;
+            }
+            Ok(Input::Click { .. }) => Ok(Key::Unknown),
+            Ok(Input::Key(key)) => Ok(key),
+            Err(error) => Err(error),
+        };
+        term.clear_last_lines(2);
+        match key {
+            Ok(Key::ArrowUp) | Ok(Key::Char('w')) | Ok(Key::Char('W')) => {
+                move_cursor(target_board, cursor, (0, -1))
+            }
+            Ok(Key::ArrowDown) | Ok(Key::Char('s')) | Ok(Key::Char('S')) => {
+                move_cursor(target_board, cursor, (0, 1))
+            }
+            Ok(Key::ArrowLeft) | Ok(Key::Char('a')) | Ok(Key::Char('A')) => {
+                move_cursor(target_board, cursor, (-1, 0))
+            }
+            Ok(Key::ArrowRight) | Ok(Key::Char('d')) | Ok(Key::Char('D')) => {
+                move_cursor(target_board, cursor, (1, 0))
+            }
+            Ok(Key::Enter) | Ok(Key::Char(' ')) => {
+                if target_board[cursor.1 as usize][cursor.0 as usize] != GridState::Blank {
+                    notice = Some("Duplicate strike.");
+                } else if let Some(index) = marked.iter().position(|point| point == cursor) {
+                    marked.remove(index);
+                } else {
+                    marked.push(cursor.clone());
+                    if marked.len() == shots {
+                        return TargetInput::Fire(marked);
                     }
                 }
-                _ => (),
             }
+            Ok(Key::Char('h')) | Ok(Key::Char('H')) => {
+                show_hint = !show_hint;
+                if show_hint && density.is_none() {
+                    density = Some(bot::heatmap(target));
+                    *hinted = true;
+                }
+            }
+            Ok(Key::Tab) | Err(_) => return type_target(term, target_board, shots, online),
+            _ => (),
         }
-        if char_pos.is_none() {
-            term.clear_last_lines(2);
-            term.write_line(&format!(
-                "{} No Letter Coordinates Provided. Enter new striking coordinates: ",
-                style(" Error ").on_red().bold()
-            ));
-            term.flush();
-            continue;
-        } else if number_pos.is_none() {
-            term.clear_last_lines(2);
-            term.write_line(&format!(
-                "{} No Numeric Coordinates Provided. Enter new striking coordinates: ",
-                style(" Error ").on_red().bold()
-            ));
-            term.flush();
-            continue;
+    }
+}
+
+/// Moves the cursor one step, skipping over cells that have already been struck.
+/// If every cell that way has been struck, it moves a single step and is flagged instead
+fn move_cursor(target_board: &[Vec<GridState>], cursor: &mut Point, (dx, dy): (i32, i32)) {
+    let in_bounds = |x: i32, y: i32| {
+        y >= 0
+            && x >= 0
+            && (y as usize) < target_board.len()
+            && (x as usize) < target_board[y as usize].len()
+    };
+    let (mut x, mut y) = (cursor.0 as i32 + dx, cursor.1 as i32 + dy);
+    if !in_bounds(x, y) {
+        return;
+    }
+    let first_step = Point(x as u8, y as u8);
+    while in_bounds(x, y) {
+        if target_board[y as usize][x as usize] == GridState::Blank {
+            *cursor = Point(x as u8, y as u8);
+            return;
         }
+        (x, y) = (x + dx, y + dy);
+    }
+    *cursor = first_step;
+}
 
-        let y = char_pos.unwrap().to_ascii_uppercase() as u8 - b'A';
-        let point = Point(number_pos.unwrap() - 1, y);
-        if target_board.board[point.1 as usize][point.0 as usize] != GridState::Blank {
-            term.clear_last_lines(2);
-            term.write_line(&format!(
-                "{} Duplicate Strike. Enter new striking coordinates: ",
-                style(" Error ").on_red().bold()
-            ));
-            term.flush();
+/// Read typed coordinates to determine the targets, one for each shot of the turn
+fn type_target(
+    term: &Term,
+    target_board: &[Vec<GridState>],
+    shots: usize,
+    online: bool,
+) -> TargetInput {
+    let request = if shots == 1 {
+        "Enter the striking coordinates".to_string()
+    } else {
+        format!("Enter {} striking coordinates, such as A1 B2", shots)
+    };
+    let commands = if online {
+        format!("{} the game", style("leave").bold())
+    } else {
+        format!(
+            "{} or {} the game",
+            style("save").bold(),
+            style("load").bold()
+        )
+    };
+    term.write_line(&format!("{} ({}): ", request, commands));
+    loop {
+        term.flush();
+        let input = term.read_line();
+        if input.is_err() {
             continue;
         }
+        let input = input.unwrap();
+        let command = match input.trim().to_ascii_lowercase().as_str() {
+            "save" if !online => Some(TargetInput::Save),
+            "load" if !online => Some(TargetInput::Load),
+            "leave" if online => Some(TargetInput::Leave),
+            _ => None,
+        };
+        if let Some(command) = command {
+            term.clear_last_lines(2);
+            return command;
+        }
+        let parsed = if shots == 1 {
+            Point::parse(&input).map(|point| vec![point])
+        } else {
+            Point::parse_list(&input)
+        };
+        let error = match parsed {
+            Err(error) => error,
+            Ok(points) => match check_targets(target_board, &points, shots) {
+                Err(error) => error,
+                Ok(()) => {
+                    term.clear_last_lines(2);
+                    term.flush();
+                    return TargetInput::Fire(points);
+                }
+            },
+        };
         term.clear_last_lines(2);
-        term.flush();
-        return point;
+        term.write_line(&format!(
+            "{} {} Enter new striking coordinates: ",
+            style(" Error ").on_red().bold(),
+            error
+        ));
     }
 }
 
-/// Randomly generate the target for the bot
-fn gen_bot_target(target_board: &GameBoard) -> Point {
-    let mut possible_positions: Vec<Point> = vec![];
-    let mut recommended_positions: Vec<Point> = vec![];
-    for i in 0..9 {
-        for j in 0..9 {
-            match target_board.board[j][i] {
-                GridState::Blank => possible_positions.push(Point(i as u8, j as u8)),
-                GridState::Hit => {
-                    // Prioritize hitting near ships that are hit but not sunk
-                    // If we have hit the same ship twice, then continue linearly
-                    let hit_ship = target_board.ships.iter().find(|ship| 
-                        ship.points.contains(&Point(i as u8, j as u8))
-                    ).expect("Ship should be found");
-                    if hit_ship.is_sunk() {
-                        continue;
-                    }
-                    // Look Up
-                    if j > 0 && target_board.board[j - 1][i] == GridState::Blank {
-                        let up = Point(i as u8, (j - 1) as u8);
-                        // If down was hit and is the same ship, then we know up must be part of the ship
-                        if j < 9 && hit_ship.hit_points.contains(&Point( i as u8, (j + 1) as u8)) {
-                            return up
-                        }
-                        recommended_positions.push(up);
-                    }
-                    // Look Down
-                    if j < 9 && target_board.board[j + 1][i] == GridState::Blank {
-                        let down = Point(i as u8, (j + 1) as u8);
-                        // If up was hit and is the same ship, then we know down must be part of the ship
-                        if j > 0 && hit_ship.hit_points.contains(&Point( i as u8, (j - 1) as u8)) {
-                            return down
-                        }
-                        recommended_positions.push(down);
-                    }
-                    // Look Left
-                    if i > 0 && target_board.board[j][i - 1] == GridState::Blank {
-                        let left = Point((i - 1) as u8, j as u8);
-                        // If right was hit and is the same ship, then we know left must be part of the ship
-                        if i < 9 && hit_ship.hit_points.contains(&Point((i+1) as u8, j as u8)) {
-                            return left;
-                        }
-                        recommended_positions.push(left);
-                    }
-                    // Look Right
-                    if i < 9 && target_board.board[j][i + 1] == GridState::Blank {
-                        let right = Point((i + 1) as u8, j as u8);
-                        // If right was hit and is the same ship, then we know left must be part of the ship
-                        if i > 0 && hit_ship.hit_points.contains(&Point((i-1) as u8, j as u8)) {
-                            return right;
-                        }
-                        recommended_positions.push(right);
-                    }
-                }
-                _ => (),
-            }
-        }
+/// Check the player's targets can all be fired at this turn
+fn check_targets(
+    target_board: &[Vec<GridState>],
+    points: &[Point],
+    shots: usize,
+) -> Result<(), &'static str> {
+    if points.len() != shots {
+        return Err("Wrong Number of Coordinates.");
     }
+    engine::check_volley(target_board, points)
+}
 
-    if !recommended_positions.is_empty() {
-        recommended_positions[rng().random_range(0..recommended_positions.len())].clone()
+/// Generates a grid
+pub fn generate_grid(
+    game_board: &GameBoard,
+    show_ships: bool,
+    crosshair: Option<&Crosshair>,
+) -> String {
+    let ship_points: Vec<&Point> = if show_ships {
+        game_board
+            .ships
+            .iter()
+            .flat_map(|ship| &ship.points)
+            .collect()
     } else {
-        possible_positions[rng().random_range(0..possible_positions.len())].clone()
-    }
+        vec![]
+    };
+    colour_grid(&game_board.board, &ship_points, crosshair)
 }
 
-/// Generates a grid
-fn generate_grid(game_board: &GameBoard, show_ships: bool) -> String {
-    let ship_points: Vec<&Point> = game_board
-        .ships
+/// Generates a grid from the shots fired at a board, showing the given ship cells and
+/// the crosshair. The cursor is yellow, or magenta when over a cell already struck, and
+/// cells marked for a salvo are green. A hint colours the blank cells from dark blue to
+/// cyan, and the recommended cell orange
+pub fn colour_grid(
+    board: &[Vec<GridState>],
+    ship_points: &[&Point],
+    crosshair: Option<&Crosshair>,
+) -> String {
+    let coloured_grid: Vec<Vec<Style>> = board
         .iter()
-        .flat_map(|ship| &ship.points)
-        .collect();
-    let coloured_grid: Vec<Vec<Style>> = (0..10)
-        .map(|i| {
-            (0..10)
-                .map(|j| match game_board.board[i][j] {
-                    GridState::Miss => Style::new().white(),
-                    GridState::Hit => Style::new().red(),
-                    GridState::Blank => {
-                        if ship_points.contains(&&Point(j as u8, i as u8)) && show_ships {
-                            if (i + j) % 2 == 0 {
-                                Style::new().black().bold()
+        .enumerate()
+        .map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(j, state)| {
+                    let point = Point(j as u8, i as u8);
+                    if let Some(crosshair) = crosshair {
+                        if crosshair.cursor == point {
+                            return if *state == GridState::Blank {
+                                Style::new().yellow().bold()
+                            } else {
+                                Style::new().magenta().bold()
+                            };
+                        }
+                        if crosshair.marked.contains(&point) {
+                            return Style::new().green().bold();
+                        }
+                        if let Some(hint) = &crosshair.hint {
+                            if hint.best == point {
+                                return Style::new().color256(208).bold();
+                            }
+                            if *state == GridState::Blank {
+                                return hint.style(&point);
+                            }
+                        }
+                    }
+                    match state {
+                        GridState::Miss => Style::new().white(),
+                        GridState::Hit => Style::new().red(),
+                        GridState::Blank => {
+                            if ship_points.contains(&&point) {
+                                if (i + j) % 2 == 0 {
+                                    Style::new().black().bold()
+                                } else {
+                                    Style::new().black()
+                                }
+                            } else if (i + j) % 2 == 0 {
+                                Style::new().blue().bold()
                             } else {
-                                Style::new().black()
+                                Style::new().blue()
                             }
-                        } else if (i + j) % 2 == 0 {
-                            Style::new().blue().bold()
-                        } else {
-                            Style::new().blue()
                         }
                     }
                 })
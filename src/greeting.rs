@@ -1,8 +1,34 @@
 use super::terminal_utils::print_center;
 use console::{Key, Term, style};
 
-/// Creates the greeting
+/// Shows the title screen, and waits for the player to start
 pub fn greet() {
+    let term = Term::buffered_stdout();
+    term.set_title("Battle Ship");
+    print_banner(&term);
+    print_center(
+        &term,
+        &format!(
+            "\n- Press {} or {} to Start -",
+            style("Space").blue().bold(),
+            style("Enter").blue().bold()
+        ),
+    );
+    term.flush();
+
+    // Start when the Space or Enter key are pressed
+    loop {
+        match term.read_key() {
+            Ok(Key::Char(' ')) | Ok(Key::Enter) | Err(_) => break,
+            _ => continue,
+        }
+    }
+    term.clear_last_lines(2);
+    term.flush();
+}
+
+/// Prints the ship and the title
+pub fn print_banner(term: &Term) {
     let ship = "                                             ..:..                              
                                                :.                               
                                                :.                               
@@ -22,30 +48,22 @@ pub fn greet() {
  / _  / _ `/ __/ __/ / -_)\ \/ _ \/ / _ \
 /____/\_,_/\__/\__/_/\__/___/_//_/_/ .__/
                                   /_/    ";
-    let term = Term::buffered_stdout();
-    term.set_title("Battle Ship");
-
-    print_center(&term, &style(ship).blue().to_string());
-    print_center(&term, &style(battleship).bold().to_string());
-    print_center(
-        &term,
-        &format!(
-            "\n- Press {} or {} to Start -",
-            style("Space").blue().bold(),
-            style("Enter").blue().bold()
-        ),
-    );
-    term.flush();
+    print_center(term, &style(ship).blue().to_string());
+    print_center(term, &style(battleship).bold().to_string());
+}
 
-    // Start when the Space or Enter key are pressed
-    loop {
-        let key = term.read_key().expect("Key unable to be read.");
-        match key {
-            Key::Char(' ') => break,
-            Key::Enter => break,
-            _ => (),
+/// Asks both players of a hot-seat game for their names
+pub fn ask_names() -> [String; 2] {
+    let term = Term::buffered_stdout();
+    [1, 2].map(|number| {
+        term.write_line(&format!("Player {}, enter your name: ", number));
+        term.flush();
+        let name = term.read_line().unwrap_or_default();
+        term.clear_last_lines(2);
+        term.flush();
+        match name.trim() {
+            "" => format!("Player {}", number),
+            name => name.to_string(),
         }
-    }
-    term.clear_last_lines(1);
-    term.flush();
+    })
 }
@@ -0,0 +1,19 @@
+//! The Battle Ship engine: boards, ships, turns and shot resolution.
+//!
+//! Nothing in this crate reads from or writes to the terminal, so the rules can be
+//! driven by any frontend. The `cl-battleship` binary is one such frontend.
+
+pub mod analysis;
+pub mod board;
+pub mod bot;
+pub mod engine;
+pub mod fair_play;
+pub mod net;
+pub mod notation;
+pub mod rules;
+pub mod replay;
+pub mod save;
+pub mod ship;
+pub mod simulation;
+pub mod stats;
+pub mod view;
@@ -1,13 +1,147 @@
 #![allow(unused)]
 
 mod create_board;
+mod files;
 mod game;
 mod greeting;
-mod ship;
+mod menu;
+mod mouse;
+mod online;
+mod replays;
 mod terminal_utils;
 
+use cl_battleship::bot::Difficulty;
+use cl_battleship::rules::{self, Rules};
+
+const USAGE: &str = "Usage: cl-battleship [--size <WIDTH>x<HEIGHT>] [--fleet <NAME>:<LENGTH>,...] [--seed <NUMBER>] [--difficulty easy|normal|hard|expert] [--salvo] [--no-touching] [--host <PORT> | --join <ADDRESS>]";
+
+/// Settings chosen on the command line
+struct Args {
+    rules: Rules,
+    /// Seeds the Admiral's layout and shots
+    seed: u64,
+    difficulty: Difficulty,
+    /// Set when playing another player over the network
+    network: Option<Network>,
+}
+
+/// How to reach the other player of an online game
+enum Network {
+    /// Wait for a player to join on a port
+    Host(u16),
+    /// Join a game hosted at an address
+    Join(String),
+}
+
+/// Reads the command line arguments
+fn parse_args() -> Result<Args, String> {
+    let defaults = Rules::default();
+    let (mut width, mut height) = (defaults.width, defaults.height);
+    let mut fleet = defaults.fleet;
+    let mut seed = rand::random();
+    let mut difficulty = Difficulty::default();
+    let mut salvo = false;
+    let mut no_touching = false;
+    let mut network = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => {
+                let size = args.next().ok_or("Missing value for --size.")?;
+                (width, height) = rules::parse_size(&size)?;
+            }
+            "--fleet" => {
+                let spec = args.next().ok_or("Missing value for --fleet.")?;
+                fleet = rules::parse_fleet(&spec)?;
+            }
+            "--seed" => {
+                let value = args.next().ok_or("Missing value for --seed.")?;
+                seed = value.parse().map_err(|_| format!("Invalid seed {}.", value))?;
+            }
+            "--difficulty" => {
+                let value = args.next().ok_or("Missing value for --difficulty.")?;
+                difficulty = Difficulty::parse(&value)?;
+            }
+            "--salvo" => salvo = true,
+            "--no-touching" => no_touching = true,
+            "--host" => {
+                let value = args.next().ok_or("Missing value for --host.")?;
+                let port = value.parse().map_err(|_| format!("Invalid port {}.", value))?;
+                network = Some(Network::Host(port));
+            }
+            "--join" => {
+                let address = args.next().ok_or("Missing value for --join.")?;
+                network = Some(Network::Join(address));
+            }
+            _ => return Err(format!("Unknown argument {}.", arg)),
+        }
+    }
+    let mut rules = Rules::build(width, height, fleet)?;
+    rules.salvo = salvo;
+    rules.no_touching = no_touching;
+    if no_touching {
+        rules.check_no_touching()?;
+    }
+    Ok(Args {
+        rules,
+        seed,
+        difficulty,
+        network,
+    })
+}
+
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            std::process::exit(2);
+        }
+    };
+    match args.network {
+        Some(Network::Host(port)) => return online::host(args.rules, port),
+        Some(Network::Join(address)) => return online::join(&address),
+        None => (),
+    }
     greeting::greet();
-    let mut game = game::Game::new();
-    game.start_game();
+    let mut settings = menu::Settings {
+        rules: args.rules,
+        difficulty: args.difficulty,
+        two_players: false,
+    };
+    let Some(mut game) = game_from_menu(&mut settings, args.seed) else {
+        return;
+    };
+    loop {
+        game.start_game();
+        // Only the first game follows the seed given on the command line
+        let seed = rand::random();
+        game = loop {
+            match menu::after_game() {
+                menu::AfterGame::Rematch => break game.rematch(seed),
+                menu::AfterGame::Review => game.review(),
+                menu::AfterGame::MainMenu => match game_from_menu(&mut settings, seed) {
+                    Some(game) => break game,
+                    None => return,
+                },
+                menu::AfterGame::Quit => return,
+            }
+        };
+    }
+}
+
+/// Shows the main menu, and sets up the game chosen there. Gives `None` if the player quits
+fn game_from_menu(settings: &mut menu::Settings, seed: u64) -> Option<game::Game> {
+    let rules = settings.rules.clone();
+    Some(match menu::main_menu(settings) {
+        menu::Choice::Continue => game::Game::resume().unwrap_or_else(|error| {
+            eprintln!("{} Starting a new game.", error);
+            game::Game::new(rules, settings.difficulty, seed)
+        }),
+        menu::Choice::NewGame if settings.two_players => {
+            game::Game::new_hot_seat(rules, greeting::ask_names(), seed)
+        }
+        menu::Choice::NewGame => game::Game::new(rules, settings.difficulty, seed),
+        menu::Choice::Quit => return None,
+    })
 }
@@ -0,0 +1,385 @@
+use cl_battleship::bot::Difficulty;
+use cl_battleship::rules::Rules;
+use cl_battleship::stats::{self, Stats};
+use console::{Key, Term, style};
+
+use crate::{files, game::Game, greeting, replays, terminal_utils::print_center};
+
+/// The settings chosen on the Options screen
+pub struct Settings {
+    pub rules: Rules,
+    pub difficulty: Difficulty,
+    /// Two players share the keyboard instead of playing the Admiral
+    pub two_players: bool,
+}
+
+/// What the player chose from the main menu
+pub enum Choice {
+    NewGame,
+    Continue,
+    Quit,
+}
+
+/// An entry of the main menu
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Item {
+    NewGame,
+    Continue,
+    Options,
+    Statistics,
+    Replays,
+    Help,
+    Quit,
+}
+
+impl Item {
+    /// Every entry, from top to bottom
+    const ALL: [Item; 7] = [
+        Item::NewGame,
+        Item::Continue,
+        Item::Options,
+        Item::Statistics,
+        Item::Replays,
+        Item::Help,
+        Item::Quit,
+    ];
+
+    /// Name of the entry
+    fn get_name(&self) -> &'static str {
+        match self {
+            Item::NewGame => "New Game",
+            Item::Continue => "Continue",
+            Item::Options => "Options",
+            Item::Statistics => "Statistics",
+            Item::Replays => "Replays",
+            Item::Help => "Help",
+            Item::Quit => "Quit",
+        }
+    }
+}
+
+/// Shows the main menu below the banner, until a game is chosen or the player quits
+pub fn main_menu(settings: &mut Settings) -> Choice {
+    let term = Term::buffered_stdout();
+    term.clear_screen().ok();
+    greeting::print_banner(&term);
+    let can_continue = Game::can_resume();
+    let items: Vec<Item> = Item::ALL
+        .into_iter()
+        .filter(|item| *item != Item::Continue || can_continue)
+        .collect();
+    let mut selected = 0;
+    loop {
+        term.write_line("");
+        items.iter().enumerate().for_each(|(i, item)| {
+            print_center(&term, &highlight(item.get_name(), i == selected));
+        });
+        term.flush();
+        let key = term.read_key();
+        term.clear_last_lines(items.len() + 1);
+        match key {
+            Ok(Key::ArrowUp) | Ok(Key::Char('w')) | Ok(Key::Char('W')) => {
+                selected = (selected + items.len() - 1) % items.len()
+            }
+            Ok(Key::ArrowDown) | Ok(Key::Char('s')) | Ok(Key::Char('S')) => {
+                selected = (selected + 1) % items.len()
+            }
+            Ok(Key::Enter) | Ok(Key::Char(' ')) => match items[selected] {
+                Item::NewGame => break Choice::NewGame,
+                Item::Continue => break Choice::Continue,
+                Item::Options => options(&term, settings),
+                Item::Statistics => statistics(&term),
+                Item::Replays => {
+                    replays::choose(&term);
+                    // Watching a replay takes over the screen
+                    term.clear_screen().ok();
+                    greeting::print_banner(&term);
+                }
+                Item::Help => help(&term),
+                Item::Quit => break Choice::Quit,
+            },
+            Ok(Key::Escape) | Ok(Key::Char('q')) | Ok(Key::Char('Q')) | Err(_) => {
+                break Choice::Quit;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Lets the player change the difficulty, the opponent and the rules
+fn options(term: &Term, settings: &mut Settings) {
+    let mut selected = 0;
+    loop {
+        let rows = [
+            format!("Difficulty: {}", settings.difficulty.get_name()),
+            format!(
+                "Opponent: {}",
+                if settings.two_players {
+                    "Second Player"
+                } else {
+                    "The Admiral"
+                }
+            ),
+            format!(
+                "Salvo: {}",
+                if settings.rules.salvo { "On" } else { "Off" }
+            ),
+            format!(
+                "No Touching: {}",
+                if settings.rules.check_no_touching().is_err() {
+                    "Unavailable"
+                } else if settings.rules.no_touching {
+                    "On"
+                } else {
+                    "Off"
+                }
+            ),
+        ];
+        term.write_line("");
+        rows.iter().enumerate().for_each(|(i, row)| {
+            print_center(term, &highlight(&format!("◀ {} ▶", row), i == selected));
+        });
+        print_center(term, &highlight("Back", selected == rows.len()));
+        term.flush();
+        let key = term.read_key();
+        term.clear_last_lines(rows.len() + 2);
+        let step = match key {
+            Ok(Key::ArrowUp) | Ok(Key::Char('w')) | Ok(Key::Char('W')) => {
+                selected = selected.saturating_sub(1);
+                continue;
+            }
+            Ok(Key::ArrowDown) | Ok(Key::Char('s')) | Ok(Key::Char('S')) => {
+                selected = (selected + 1).min(rows.len());
+                continue;
+            }
+            Ok(Key::ArrowLeft) | Ok(Key::Char('a')) | Ok(Key::Char('A')) => -1,
+            Ok(Key::ArrowRight) | Ok(Key::Char('d')) | Ok(Key::Char('D')) => 1,
+            Ok(Key::Enter) | Ok(Key::Char(' ')) if selected == rows.len() => return,
+            Ok(Key::Enter) | Ok(Key::Char(' ')) => 1,
+            Ok(Key::Escape) | Err(_) => return,
+            _ => continue,
+        };
+        match selected {
+            0 if step < 0 => settings.difficulty = settings.difficulty.previous(),
+            0 => settings.difficulty = settings.difficulty.next(),
+            1 => settings.two_players = !settings.two_players,
+            2 => settings.rules.salvo = !settings.rules.salvo,
+            // Only fleets with room to keep apart can be played without touching
+            3 => {
+                settings.rules.no_touching =
+                    !settings.rules.no_touching && settings.rules.check_no_touching().is_ok()
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Shows what has been recorded about past games, and offers to reset it
+fn statistics(term: &Term) {
+    let path = files::stats_path();
+    let stats = match stats::load_stats(&path) {
+        Ok(stats) => stats,
+        Err(error) => {
+            return show(term, &[format!("{} {}", style(" Error ").on_red().bold(), error)]);
+        }
+    };
+    let lines = describe_stats(&stats);
+    term.write_line("");
+    lines.iter().for_each(|line| print_center(term, line));
+    term.write_line("");
+    print_center(
+        term,
+        &format!(
+            "- Press {} to go back, or {} to reset -",
+            style("Enter").blue().bold(),
+            style("R").blue().bold()
+        ),
+    );
+    term.flush();
+    let reset = loop {
+        match term.read_key() {
+            Ok(Key::Char('r')) | Ok(Key::Char('R')) => break true,
+            Ok(Key::Enter) | Ok(Key::Escape) | Ok(Key::Char(' ')) | Err(_) => break false,
+            _ => (),
+        }
+    };
+    term.clear_last_lines(lines.len() + 3);
+    if reset && confirm(term, "Reset every statistic? This can't be undone.") {
+        let message = match stats::save_stats(&path, &Stats::default()) {
+            Ok(()) => "The statistics have been reset.".to_string(),
+            Err(error) => format!("{} {}", style(" Error ").on_red().bold(), error),
+        };
+        show(term, &[message]);
+    }
+}
+
+/// The statistics as lines of text
+fn describe_stats(stats: &Stats) -> Vec<String> {
+    if stats.played == 0 {
+        return vec!["No games against the Admiral have been finished yet.".to_string()];
+    }
+    let sunk_first: Vec<String> = stats
+        .sunk_first_ranking()
+        .iter()
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect();
+    vec![
+        format!("{}", style("Statistics").bold()),
+        format!(
+            "Games played: {}   Wins: {}   Losses: {}",
+            stats.played, stats.wins, stats.losses
+        ),
+        format!(
+            "Win streak: {}   Best streak: {}",
+            stats.streak, stats.best_streak
+        ),
+        format!(
+            "Accuracy: {:.0}% ({} hits from {} shots)",
+            stats.accuracy().unwrap_or(0.0) * 100.0,
+            stats.hits,
+            stats.shots
+        ),
+        format!("Hints used: {}", stats.hints),
+        match (stats.average_shots_to_win(), stats.fastest_win) {
+            (Some(average), Some(fastest)) => format!(
+                "Average shots to win: {:.1}   Fastest win: {} shots",
+                average, fastest
+            ),
+            _ => "No wins yet.".to_string(),
+        },
+        if sunk_first.is_empty() {
+            "None of your ships have been sunk.".to_string()
+        } else {
+            format!("Sunk first: {}", sunk_first.join(", "))
+        },
+    ]
+}
+
+/// Asks a yes or no question
+fn confirm(term: &Term, question: &str) -> bool {
+    term.write_line("");
+    print_center(
+        term,
+        &format!(
+            "{} Press {} to confirm, or any other key to cancel.",
+            question,
+            style("Y").blue().bold()
+        ),
+    );
+    term.flush();
+    let key = term.read_key();
+    term.clear_last_lines(2);
+    matches!(key, Ok(Key::Char('y')) | Ok(Key::Char('Y')))
+}
+
+/// Explains the rules and the controls
+fn help(term: &Term) {
+    show(
+        term,
+        &[
+            format!("{}", style("How to Play").bold()),
+            "Place your fleet, then take turns firing at the enemy's Target Board.".to_string(),
+            "Sink every enemy ship before they sink yours to win.".to_string(),
+            "In Salvo games, each turn fires one shot for every ship still afloat.".to_string(),
+            "With No Touching, ships may not touch and the water around sunk ships is marked."
+                .to_string(),
+            String::new(),
+            format!("{}", style("Controls").bold()),
+            "Arrow keys or WASD to move, Enter or Space to place or fire.".to_string(),
+            "Click a cell to move a ship or fire there, and right click to rotate a ship.".to_string(),
+            "Tab to type coordinates, or to save or load the game while firing.".to_string(),
+            "H while firing shows where the enemy ships most likely are.".to_string(),
+        ],
+    );
+}
+
+/// Shows lines of text until the player goes back
+pub fn show(term: &Term, lines: &[String]) {
+    term.write_line("");
+    lines.iter().for_each(|line| print_center(term, line));
+    term.write_line("");
+    print_center(
+        term,
+        &format!("- Press {} to go back -", style("Enter").blue().bold()),
+    );
+    term.flush();
+    loop {
+        match term.read_key() {
+            Ok(Key::Enter) | Ok(Key::Escape) | Ok(Key::Char(' ')) | Err(_) => break,
+            _ => (),
+        }
+    }
+    term.clear_last_lines(lines.len() + 3);
+}
+
+/// What the player chose once a game is over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AfterGame {
+    Rematch,
+    /// Rate the shots of the game that just ended
+    Review,
+    MainMenu,
+    Quit,
+}
+
+impl AfterGame {
+    /// Every choice, from left to right
+    const ALL: [AfterGame; 4] = [
+        AfterGame::Rematch,
+        AfterGame::Review,
+        AfterGame::MainMenu,
+        AfterGame::Quit,
+    ];
+
+    /// Name of the choice
+    fn get_name(&self) -> &'static str {
+        match self {
+            AfterGame::Rematch => "Rematch",
+            AfterGame::Review => "Review Shots",
+            AfterGame::MainMenu => "Main Menu",
+            AfterGame::Quit => "Quit",
+        }
+    }
+}
+
+/// Asks what to do once a game is over
+pub fn after_game() -> AfterGame {
+    let term = Term::buffered_stdout();
+    let mut selected = 0;
+    loop {
+        let choices: Vec<String> = AfterGame::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| highlight(choice.get_name(), i == selected))
+            .collect();
+        print_center(&term, &choices.join("   "));
+        term.flush();
+        let key = term.read_key();
+        term.clear_last_lines(1);
+        match key {
+            Ok(Key::ArrowLeft) | Ok(Key::Char('a')) | Ok(Key::Char('A')) => {
+                selected = selected.saturating_sub(1)
+            }
+            Ok(Key::ArrowRight) | Ok(Key::Char('d')) | Ok(Key::Char('D')) => {
+                selected = (selected + 1).min(AfterGame::ALL.len() - 1)
+            }
+            Ok(Key::Enter) | Ok(Key::Char(' ')) => break AfterGame::ALL[selected],
+            Ok(Key::Char('r')) | Ok(Key::Char('R')) => break AfterGame::Rematch,
+            Ok(Key::Char('v')) | Ok(Key::Char('V')) => break AfterGame::Review,
+            Ok(Key::Char('m')) | Ok(Key::Char('M')) => break AfterGame::MainMenu,
+            Ok(Key::Escape) | Ok(Key::Char('q')) | Ok(Key::Char('Q')) | Err(_) => {
+                break AfterGame::Quit;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Marks the selected entry of a menu
+pub fn highlight(text: &str, selected: bool) -> String {
+    if selected {
+        style(format!(" {} ", text)).on_blue().bold().to_string()
+    } else {
+        format!(" {} ", text)
+    }
+}
@@ -0,0 +1,164 @@
+//! Mouse clicks on the grids.
+//!
+//! `console` only reads keys, so mouse reporting is switched on with escape codes while a
+//! key is awaited, and each report is pieced back together from the keys it arrives as.
+//! Reports give the click's position on the screen, so the grids are kept against the
+//! bottom of the screen (see `anchor`) where their position can be worked out.
+
+use cl_battleship::ship::Point;
+use console::{Key, Term};
+use std::io;
+
+use crate::terminal_utils::row_label_width;
+
+/// Reports button presses in the SGR format, which has no limit on the screen size
+const ENABLE: &str = "\x1b[?1000h\x1b[?1006h";
+const DISABLE: &str = "\x1b[?1006l\x1b[?1000l";
+
+/// A key press or a mouse click
+pub enum Input {
+    Key(Key),
+    /// A click at a 1-based column and row of the screen
+    Click {
+        button: Button,
+        column: usize,
+        row: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Button {
+    Left,
+    Right,
+}
+
+/// Reads a key press or a mouse click. Anything else the mouse reports reads as `Key::Unknown`
+pub fn read_input(term: &Term) -> io::Result<Input> {
+    term.write_str(ENABLE)?;
+    term.flush()?;
+    let input = match term.read_key() {
+        Ok(Key::UnknownEscSeq(sequence)) if sequence.starts_with(&['[', '<']) => {
+            read_click(term, sequence[2..].iter().collect())
+        }
+        key => key.map(Input::Key),
+    };
+    term.write_str(DISABLE)?;
+    term.flush()?;
+    input
+}
+
+/// Reads the rest of a report such as `\x1b[<0;12;5M`, given the characters after `<`
+fn read_click(term: &Term, mut report: String) -> io::Result<Input> {
+    while !report.ends_with(['M', 'm']) {
+        match term.read_key()? {
+            Key::Char(character) => report.push(character),
+            _ => return Ok(Input::Key(Key::Unknown)),
+        }
+    }
+    Ok(parse_report(&report).unwrap_or(Input::Key(Key::Unknown)))
+}
+
+/// Parses a report of a button press. Releases, motion and the wheel give `None`
+fn parse_report(report: &str) -> Option<Input> {
+    let fields = report.strip_suffix('M')?;
+    let mut fields = fields.split(';').map(|field| field.parse::<usize>().ok());
+    let (code, column, row) = (fields.next()??, fields.next()??, fields.next()??);
+    // Bit 32 is motion and bit 64 is the wheel
+    if code & (32 | 64) != 0 {
+        return None;
+    }
+    let button = match code & 3 {
+        0 => Button::Left,
+        2 => Button::Right,
+        _ => return None,
+    };
+    Some(Input::Click {
+        button,
+        column,
+        row,
+    })
+}
+
+/// Scrolls everything up so the cursor is on the bottom line of the screen.
+/// Anything written from then on has a known position on the screen
+pub fn anchor(term: &Term) {
+    let (height, _) = term.size();
+    term.write_str(&"\n".repeat(height as usize));
+    term.flush();
+}
+
+/// Finds the cell under a click on a grid from `create_colored_grid`.
+/// The grid's first line is `lines_up` lines above the cursor, which is on the bottom line
+/// of the screen, and its rows start `indent` columns in
+pub fn grid_cell(
+    term: &Term,
+    (column, row): (usize, usize),
+    lines_up: usize,
+    indent: usize,
+    (columns, rows): (u8, u8),
+) -> Option<Point> {
+    let (height, _) = term.size();
+    let top = (height as usize).checked_sub(lines_up)?;
+    cell_at(column, row, top, indent, columns, rows)
+}
+
+/// Finds the cell at a 1-based screen position, when the grid's first line is on screen row
+/// `top`. Each cell is 4 characters wide and 2 lines tall, below the blank line and header
+fn cell_at(
+    column: usize,
+    row: usize,
+    top: usize,
+    indent: usize,
+    columns: u8,
+    rows: u8,
+) -> Option<Point> {
+    let line = row.checked_sub(top + 2)?;
+    let x = column
+        .saturating_sub(1)
+        .checked_sub(indent + row_label_width(rows))?;
+    let (cell_x, cell_y) = (x / 4, line / 2);
+    if cell_x < columns as usize && cell_y < rows as usize {
+        Some(Point(cell_x as u8, cell_y as u8))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reports_map_to_cells() {
+        assert!(matches!(
+            parse_report("0;12;5M"),
+            Some(Input::Click {
+                button: Button::Left,
+                column: 12,
+                row: 5
+            })
+        ));
+        assert!(matches!(
+            parse_report("2;1;1M"),
+            Some(Input::Click {
+                button: Button::Right,
+                ..
+            })
+        ));
+        // Releases, motion and the wheel
+        assert!(parse_report("0;12;5m").is_none());
+        assert!(parse_report("32;12;5M").is_none());
+        assert!(parse_report("64;12;5M").is_none());
+
+        // A 10 by 10 grid at the top left: the labels take 3 columns, and row A starts on line 3
+        assert_eq!(cell_at(4, 3, 1, 0, 10, 10), Some(Point(0, 0)));
+        assert_eq!(cell_at(7, 4, 1, 0, 10, 10), Some(Point(0, 0)));
+        assert_eq!(cell_at(8, 5, 1, 0, 10, 10), Some(Point(1, 1)));
+        assert_eq!(cell_at(43, 22, 1, 0, 10, 10), Some(Point(9, 9)));
+        // The labels, the header, and past the last cell
+        assert_eq!(cell_at(3, 3, 1, 0, 10, 10), None);
+        assert_eq!(cell_at(4, 2, 1, 0, 10, 10), None);
+        assert_eq!(cell_at(44, 3, 1, 0, 10, 10), None);
+        assert_eq!(cell_at(4, 23, 1, 0, 10, 10), None);
+    }
+}
@@ -0,0 +1,637 @@
+//! Playing against another instance of the game over TCP.
+//!
+//! Each side keeps its own `GameBoard` and only ever learns what its shots revealed.
+//! The two sides exchange one message per line, as UTF-8 text:
+//!
+//! | Message                 | Meaning                                                      |
+//! |-------------------------|--------------------------------------------------------------|
+//! | `HELLO <version>`       | Sent by both sides on connecting, with `PROTOCOL_VERSION`    |
+//! | `RULES <json>`          | Sent by the host after the hellos: the `Rules` as JSON,      |
+//! |                         | including salvo and no touching                              |
+//! | `READY <commitment>`    | Sent by both sides once their fleet is placed, with a hash   |
+//! |                         | committing to the layout, see `fair_play`                    |
+//! | `FIRE <point> ...`      | The shooter's targets for the turn, such as `FIRE B7 C3`     |
+//! | `MISS`                  | A reply to one target of a `FIRE`                            |
+//! | `HIT <len> <name>`      | A reply to one target: a ship was hit but is still afloat    |
+//! | `SUNK <len> <name>`     | A reply to one target: the shot sank that ship               |
+//! | `DESTROYED <len> <name>`| A reply to one target: the shot sank the last ship           |
+//! | `REVEAL <salt> <json>`  | Sent by both sides once the game is over: the fleet layout   |
+//! |                         | and salt behind the commitment                               |
+//! | `ERROR <text>`          | The sender found a problem and is closing the connection     |
+//! | `BYE`                   | The sender is leaving the game                               |
+//!
+//! The host fires first. Every target of a `FIRE` gets one reply, in order, unless the
+//! fleet is destroyed part way through a volley, after which no more replies are sent.
+//! Both sides then reveal their fleets, so each can check the other's replies were honest.
+//! Replies that could not be true of any fleet are caught as they arrive, and answered with
+//! `ERROR`, so a side can't escape the reveal by never admitting its fleet was destroyed.
+//! A side that receives a `HELLO` with another version replies with `ERROR` and closes.
+//! Replies that should come straight back time out after `REPLY_TIMEOUT`, while waiting on
+//! the other player to place their fleet or choose targets times out after `TURN_TIMEOUT`.
+
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use super::board::{GameBoard, ShotOutcome};
+use super::engine::{Side, check_volley, shots_per_turn};
+use super::fair_play::{self, SealedFleet, Verdict};
+use super::rules::Rules;
+use super::ship::{Point, ShipKind};
+use super::view::BoardView;
+
+/// Version of the protocol spoken by this build. Both sides must match
+pub const PROTOCOL_VERSION: u32 = 3;
+/// The port used when none is given
+pub const DEFAULT_PORT: u16 = 7373;
+/// How long to wait for a message the other side sends without asking its player
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long to wait for the other player to place their fleet or choose their targets
+pub const TURN_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// One line of the protocol
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Hello { version: u32 },
+    Rules(Rules),
+    /// Holds the commitment to the sender's fleet
+    Ready(String),
+    Fire(Vec<Point>),
+    Result(ShotOutcome),
+    Reveal(SealedFleet),
+    Error(String),
+    Bye,
+}
+
+impl Message {
+    /// Parses a line of the protocol, without its line ending
+    pub fn parse(line: &str) -> Result<Self, &'static str> {
+        let (command, rest) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match command {
+            "HELLO" => Ok(Message::Hello {
+                version: rest.parse().map_err(|_| "Invalid protocol version.")?,
+            }),
+            "RULES" => Ok(Message::Rules(
+                serde_json::from_str(rest).map_err(|_| "Invalid rules.")?,
+            )),
+            "READY" if !rest.is_empty() => Ok(Message::Ready(rest.to_string())),
+            "READY" => Err("Missing commitment."),
+            "FIRE" => Ok(Message::Fire(Point::parse_list(rest)?)),
+            "MISS" => Ok(Message::Result(ShotOutcome::Miss)),
+            "HIT" => Ok(Message::Result(ShotOutcome::Hit {
+                kind: parse_kind(rest)?,
+            })),
+            "SUNK" => Ok(Message::Result(ShotOutcome::Sunk {
+                kind: parse_kind(rest)?,
+            })),
+            "DESTROYED" => Ok(Message::Result(ShotOutcome::FleetDestroyed {
+                kind: parse_kind(rest)?,
+            })),
+            "REVEAL" => {
+                let (salt, fleet) = rest.split_once(' ').ok_or("Invalid reveal.")?;
+                Ok(Message::Reveal(SealedFleet {
+                    salt: salt.to_string(),
+                    fleet: fleet.to_string(),
+                }))
+            }
+            "ERROR" => Ok(Message::Error(rest.to_string())),
+            "BYE" => Ok(Message::Bye),
+            _ => Err("Unknown message."),
+        }
+    }
+}
+
+impl std::fmt::Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Message::Hello { version } => write!(f, "HELLO {}", version),
+            Message::Rules(rules) => write!(
+                f,
+                "RULES {}",
+                serde_json::to_string(rules).map_err(|_| std::fmt::Error)?
+            ),
+            Message::Ready(commitment) => write!(f, "READY {}", commitment),
+            Message::Fire(targets) => {
+                write!(f, "FIRE")?;
+                targets.iter().try_for_each(|target| write!(f, " {}", target))
+            }
+            Message::Result(ShotOutcome::Miss) => write!(f, "MISS"),
+            Message::Result(ShotOutcome::Hit { kind }) => write!(f, "HIT {}", format_kind(kind)),
+            Message::Result(ShotOutcome::Sunk { kind }) => write!(f, "SUNK {}", format_kind(kind)),
+            Message::Result(ShotOutcome::FleetDestroyed { kind }) => {
+                write!(f, "DESTROYED {}", format_kind(kind))
+            }
+            Message::Reveal(sealed) => write!(f, "REVEAL {} {}", sealed.salt, sealed.fleet),
+            // Keep the text on one line
+            Message::Error(text) => write!(f, "ERROR {}", text.replace(['\r', '\n'], " ")),
+            Message::Bye => write!(f, "BYE"),
+        }
+    }
+}
+
+/// Writes a ship as its length then its name, since names may hold spaces
+fn format_kind(kind: &ShipKind) -> String {
+    format!("{} {}", kind.get_len(), kind.get_name())
+}
+
+/// Reads a ship written by `format_kind`
+fn parse_kind(text: &str) -> Result<ShipKind, &'static str> {
+    let (len, name) = text.split_once(' ').ok_or("Invalid ship.")?;
+    let len = len.parse().map_err(|_| "Invalid ship.")?;
+    ShipKind::build(name, len)
+}
+
+/// A connection to the other player's game
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    stream: TcpStream,
+}
+
+impl Connection {
+    /// Waits for the other player to connect to the listener
+    pub fn accept(listener: &TcpListener) -> Result<Self, &'static str> {
+        let (stream, _) = listener
+            .accept()
+            .map_err(|_| "Unable to accept a connection.")?;
+        Self::from_stream(stream)
+    }
+
+    /// Connects to a game hosted at the address, such as "192.168.1.20:7373"
+    pub fn connect(address: impl ToSocketAddrs) -> Result<Self, &'static str> {
+        let addresses = address
+            .to_socket_addrs()
+            .map_err(|_| "Unable to find the host.")?;
+        for address in addresses {
+            if let Ok(stream) = TcpStream::connect_timeout(&address, REPLY_TIMEOUT) {
+                return Self::from_stream(stream);
+            }
+        }
+        Err("Unable to connect to the host.")
+    }
+
+    fn from_stream(stream: TcpStream) -> Result<Self, &'static str> {
+        // Every message is small and waited on, so send each at once
+        stream.set_nodelay(true).ok();
+        let reader = stream
+            .try_clone()
+            .map_err(|_| "Unable to set up the connection.")?;
+        Ok(Self {
+            reader: BufReader::new(reader),
+            stream,
+        })
+    }
+
+    /// Sends one message
+    pub fn send(&mut self, message: &Message) -> Result<(), &'static str> {
+        writeln!(self.stream, "{}", message).map_err(|_| "The other player disconnected.")
+    }
+
+    /// Waits up to `timeout` for the next message. Errors and goodbyes from the other
+    /// side are returned as errors
+    pub fn receive(&mut self, timeout: Duration) -> Result<Message, &'static str> {
+        self.stream.set_read_timeout(Some(timeout)).ok();
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => return Err("The other player disconnected."),
+            Ok(_) => (),
+            Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Err("The other player stopped responding.");
+            }
+            Err(_) => return Err("The other player disconnected."),
+        }
+        match Message::parse(&line) {
+            Ok(Message::Error(_)) => Err("The other player's game reported an error."),
+            Ok(Message::Bye) => Err("The other player left the game."),
+            Ok(message) => Ok(message),
+            Err(error) => {
+                self.send(&Message::Error(error.to_string())).ok();
+                Err("Received an invalid message.")
+            }
+        }
+    }
+
+    /// Reports a problem to the other side before giving up on the connection
+    fn fail<T>(&mut self, error: &'static str) -> Result<T, &'static str> {
+        self.send(&Message::Error(error.to_string())).ok();
+        Err(error)
+    }
+
+    /// Swaps hellos, refusing a game of another protocol version
+    fn greet(&mut self) -> Result<(), &'static str> {
+        self.send(&Message::Hello {
+            version: PROTOCOL_VERSION,
+        })?;
+        match self.receive(REPLY_TIMEOUT)? {
+            Message::Hello { version } if version == PROTOCOL_VERSION => Ok(()),
+            Message::Hello { .. } => {
+                self.fail("The other player is running a different version of the game.")
+            }
+            _ => self.fail("Received an unexpected message."),
+        }
+    }
+
+    /// Greets a player that joined, and tells them the rules
+    pub fn host_handshake(&mut self, rules: &Rules) -> Result<(), &'static str> {
+        self.greet()?;
+        self.send(&Message::Rules(rules.clone()))
+    }
+
+    /// Greets the host, and learns the rules of their game
+    pub fn join_handshake(&mut self) -> Result<Rules, &'static str> {
+        self.greet()?;
+        match self.receive(REPLY_TIMEOUT)? {
+            Message::Rules(rules) => {
+                // Rules from the wire are checked like any others, and their ships were
+                // checked as the message was read
+                match rules.check() {
+                    Ok(()) => Ok(rules),
+                    Err(error) => self.fail(error),
+                }
+            }
+            _ => self.fail("Received an unexpected message."),
+        }
+    }
+}
+
+/// A game against another player over a connection. The player is `Side::Player`
+/// and the other player is `Side::Opponent`
+pub struct RemoteGame {
+    connection: Connection,
+    rules: Rules,
+    /// This player's fleet, which the other side never sees
+    pub own_board: GameBoard,
+    /// What this player has learned about the other player's fleet
+    pub target: BoardView,
+    turn: Side,
+    winner: Option<Side>,
+    /// This player's fleet as committed to at the start
+    sealed: SealedFleet,
+    /// The other player's commitment to their fleet
+    commitment: String,
+    /// Every shot this player fired, with the outcome the other side reported
+    fired: Vec<(Point, ShotOutcome)>,
+    /// Why the other side's replies were found to be lies before the game could end
+    caught: Option<&'static str>,
+}
+
+impl RemoteGame {
+    /// Tells the other side this player's fleet is placed and waits until theirs is.
+    /// The host fires first
+    pub fn start(
+        mut connection: Connection,
+        rules: Rules,
+        own_board: GameBoard,
+        is_host: bool,
+    ) -> Result<Self, &'static str> {
+        let sealed = SealedFleet::seal(&own_board.ships);
+        connection.send(&Message::Ready(sealed.commitment()))?;
+        let commitment = match connection.receive(TURN_TIMEOUT)? {
+            Message::Ready(commitment) => commitment,
+            _ => return connection.fail("Received an unexpected message."),
+        };
+        Ok(Self {
+            connection,
+            target: BoardView::new(rules.width, rules.height, rules.fleet.clone()),
+            rules,
+            own_board,
+            turn: if is_host { Side::Player } else { Side::Opponent },
+            winner: None,
+            sealed,
+            commitment,
+            fired: vec![],
+            caught: None,
+        })
+    }
+
+    /// The rules the game is played by
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// The side that fires next
+    pub fn turn(&self) -> Side {
+        self.turn
+    }
+
+    /// The side that sunk the whole enemy fleet, if any
+    pub fn winner(&self) -> Option<Side> {
+        self.winner
+    }
+
+    /// Why the other player was caught lying before their fleet was destroyed, if they were
+    pub fn caught(&self) -> Option<&'static str> {
+        self.caught
+    }
+
+    /// Check weather the game has been won, or ended by the other player being caught lying
+    pub fn is_game_over(&self) -> bool {
+        self.winner.is_some() || self.caught.is_some()
+    }
+
+    /// Shots the side whose turn it is fires this turn
+    pub fn shots_per_turn(&self) -> usize {
+        match self.turn {
+            Side::Player => shots_per_turn(&self.rules, self.own_board.ships_left, &self.target.board),
+            Side::Opponent => shots_per_turn(
+                &self.rules,
+                self.target.remaining().len() as u8,
+                &self.own_board.board,
+            ),
+        }
+    }
+
+    /// Fires this player's volley and waits for the other side to report what it hit
+    pub fn fire(&mut self, targets: &[Point]) -> Result<Vec<ShotOutcome>, &'static str> {
+        if self.is_game_over() {
+            return Err("The game is over.");
+        }
+        if self.turn != Side::Player {
+            return Err("It is not your turn.");
+        }
+        if targets.len() != self.shots_per_turn() {
+            return Err("Wrong number of shots.");
+        }
+        check_volley(&self.target.board, targets)?;
+        self.connection.send(&Message::Fire(targets.to_vec()))?;
+
+        let mut outcomes = vec![];
+        for target in targets {
+            let outcome = match self.connection.receive(REPLY_TIMEOUT)? {
+                Message::Result(outcome) => outcome,
+                _ => return self.connection.fail("Received an unexpected message."),
+            };
+            self.target.record(target, &outcome);
+            self.fired.push((target.clone(), outcome.clone()));
+            let destroyed = matches!(outcome, ShotOutcome::FleetDestroyed { .. });
+            outcomes.push(outcome);
+            if destroyed {
+                self.winner = Some(Side::Player);
+                return Ok(outcomes);
+            }
+        }
+        // A side that never admits to losing its fleet can't be left to the reveal
+        if let Err(reason) = fair_play::check_replies(&self.rules, &self.fired) {
+            self.caught = Some(reason);
+            self.connection
+                .send(&Message::Error(reason.to_string()))
+                .ok();
+            return Ok(outcomes);
+        }
+        // The water around sunk ships is marked once the whole volley has landed
+        if self.rules.no_touching && outcomes.iter().any(|outcome| outcome.is_sunk()) {
+            self.target.mark_around_sunk();
+        }
+        self.turn = Side::Opponent;
+        Ok(outcomes)
+    }
+
+    /// Waits for the other player's volley, fires it at this player's fleet and reports
+    /// what it hit
+    pub fn receive_fire(&mut self) -> Result<(Vec<Point>, Vec<ShotOutcome>), &'static str> {
+        if self.is_game_over() {
+            return Err("The game is over.");
+        }
+        if self.turn != Side::Opponent {
+            return Err("It is not the other player's turn.");
+        }
+        let targets = match self.connection.receive(TURN_TIMEOUT)? {
+            Message::Fire(targets) => targets,
+            _ => return self.connection.fail("Received an unexpected message."),
+        };
+        if targets.len() != self.shots_per_turn()
+            || check_volley(&self.own_board.board, &targets).is_err()
+        {
+            return self.connection.fail("The other player fired an invalid volley.");
+        }
+
+        let mut outcomes = vec![];
+        for target in &targets {
+            let outcome = self.own_board.strike(target)?;
+            self.connection.send(&Message::Result(outcome.clone()))?;
+            let destroyed = matches!(outcome, ShotOutcome::FleetDestroyed { .. });
+            outcomes.push(outcome);
+            if destroyed {
+                self.winner = Some(Side::Opponent);
+                return Ok((targets, outcomes));
+            }
+        }
+        if self.rules.no_touching {
+            for (target, outcome) in targets.iter().zip(&outcomes) {
+                if outcome.is_sunk() {
+                    self.own_board.mark_around_sunk(target);
+                }
+            }
+        }
+        self.turn = Side::Player;
+        Ok((targets, outcomes))
+    }
+
+    /// Once a fleet is destroyed, swaps fleets with the other side and checks every outcome
+    /// it reported against the fleet it committed to
+    pub fn reveal(&mut self) -> Result<Verdict, &'static str> {
+        if self.winner.is_none() {
+            return Err("The game is not over.");
+        }
+        self.connection.send(&Message::Reveal(self.sealed.clone()))?;
+        match self.connection.receive(REPLY_TIMEOUT)? {
+            Message::Reveal(revealed) => Ok(fair_play::verify(
+                &self.rules,
+                &revealed,
+                &self.commitment,
+                &self.fired,
+            )),
+            _ => self.connection.fail("Received an unexpected message."),
+        }
+    }
+
+    /// Tells the other side this player is leaving
+    pub fn leave(mut self) {
+        if !self.is_game_over() {
+            self.connection.send(&Message::Bye).ok();
+        }
+    }
+}
+
+/// Listens for a player to join on the port, on every network interface
+pub fn listen(port: u16) -> Result<TcpListener, &'static str> {
+    TcpListener::bind(("0.0.0.0", port)).map_err(|_| "Unable to listen on that port.")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::generate_game_board;
+    use crate::bot::Difficulty;
+    use crate::ship::{Ship, ShipDirection};
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::thread;
+
+    #[test]
+    fn messages_round_trip_through_lines() {
+        let kind = ShipKind::build("Patrol Boat", 2).unwrap();
+        let messages = [
+            Message::Hello { version: 7 },
+            Message::Rules(Rules::default()),
+            Message::Ready("9f86d081884c7d65".to_string()),
+            Message::Fire(vec![Point(6, 1), Point(0, 26)]),
+            Message::Result(ShotOutcome::Miss),
+            Message::Result(ShotOutcome::Sunk { kind: kind.clone() }),
+            Message::Result(ShotOutcome::FleetDestroyed { kind }),
+            Message::Reveal(SealedFleet {
+                salt: "00ff".to_string(),
+                fleet: r#"[{"x":1,"y":2}]"#.to_string(),
+            }),
+            Message::Error("Out of sync.".to_string()),
+            Message::Bye,
+        ];
+        for message in messages {
+            assert_eq!(Message::parse(&message.to_string()), Ok(message));
+        }
+        assert_eq!(Message::parse("FIRE B7"), Ok(Message::Fire(vec![Point(6, 1)])));
+        assert_eq!(Message::parse("LAUNCH"), Err("Unknown message."));
+        assert_eq!(Message::parse("HIT Cruiser"), Err("Invalid ship."));
+        assert_eq!(
+            Message::parse("HIT 2 Patrol\u{7}Boat"),
+            Err("Ship names can't hold control characters.")
+        );
+
+        // Ships in the rules are checked like any others
+        let rules = r#"RULES {"width":10,"height":10,"fleet":[{"name":"Raft","len":0}]}"#;
+        assert_eq!(Message::parse(rules), Err("Invalid rules."));
+        let rules = r#"RULES {"width":10,"height":10,"fleet":[{"name":"Ra\nft","len":1}]}"#;
+        assert_eq!(Message::parse(rules), Err("Invalid rules."));
+    }
+
+    /// Plays one side of a game on loopback, with the Admiral choosing every volley
+    fn play(connection: Connection, rules: Rules, is_host: bool, seed: u64) -> Option<Side> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let board = generate_game_board(&rules, &mut rng);
+        let mut game = RemoteGame::start(connection, rules, board, is_host).unwrap();
+        while !game.is_game_over() {
+            if game.turn() == Side::Player {
+                let targets =
+                    Difficulty::Hard.gen_volley(&game.target, game.shots_per_turn(), &mut rng);
+                game.fire(&targets).unwrap();
+            } else {
+                game.receive_fire().unwrap();
+            }
+        }
+        assert!(matches!(game.reveal(), Ok(Verdict::Fair(_))));
+        game.winner()
+    }
+
+    #[test]
+    fn games_are_played_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let rules = Rules {
+            salvo: true,
+            ..Rules::default()
+        };
+        let host_rules = rules.clone();
+        let host = thread::spawn(move || {
+            let mut connection = Connection::accept(&listener).unwrap();
+            connection.host_handshake(&host_rules).unwrap();
+            play(connection, host_rules, true, 1)
+        });
+
+        let mut connection = Connection::connect(address).unwrap();
+        assert_eq!(connection.join_handshake(), Ok(rules.clone()));
+        let joined = play(connection, rules, false, 2);
+        let hosted = host.join().unwrap();
+        // Both sides agree on who won, from their own point of view
+        assert!(joined.is_some());
+        assert_eq!(hosted, joined.map(|side| side.other()));
+    }
+
+    #[test]
+    fn lies_are_caught_before_the_fleet_is_destroyed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let rules = Rules::build(5, 5, vec![ShipKind::build("Destroyer", 2).unwrap()]).unwrap();
+        let host_rules = rules.clone();
+        let host = thread::spawn(move || {
+            let mut connection = Connection::accept(&listener).unwrap();
+            connection.host_handshake(&host_rules).unwrap();
+            let destroyer = host_rules.fleet[0].clone();
+            let ship = Ship::build(destroyer, 0, 0, ShipDirection::Up, &host_rules).unwrap();
+            let board = GameBoard::build(vec![ship], &host_rules);
+            let mut game = RemoteGame::start(connection, host_rules, board, true).unwrap();
+            let mut cells = (0..25).map(|i| Point(i % 5, i / 5));
+            while !game.is_game_over() {
+                if game.turn() == Side::Player {
+                    game.fire(&[cells.next().unwrap()]).unwrap();
+                } else {
+                    game.receive_fire().unwrap();
+                }
+            }
+            (game.winner(), game.caught())
+        });
+
+        // A player who says every shot missed, and fires back from the far corner
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut line = String::new();
+        writeln!(stream, "HELLO {}", PROTOCOL_VERSION).unwrap();
+        writeln!(stream, "READY {}", "0".repeat(64)).unwrap();
+        let mut cells = (0..25).rev().map(|i| Point(i % 5, i / 5));
+        loop {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            if line.starts_with("FIRE") {
+                writeln!(stream, "MISS").unwrap();
+                writeln!(stream, "{}", Message::Fire(vec![cells.next().unwrap()])).unwrap();
+            } else if line.starts_with("ERROR") {
+                break;
+            }
+        }
+        assert_eq!(
+            host.join().unwrap(),
+            (
+                None,
+                Some("The misses reported leave no room for the rest of the fleet.")
+            )
+        );
+    }
+
+    #[test]
+    fn mismatched_versions_and_disconnects_are_reported() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let host = thread::spawn(move || {
+            let mut connection = Connection::accept(&listener).unwrap();
+            connection.host_handshake(&Rules::default())
+        });
+
+        // A client from the future
+        let mut stream = TcpStream::connect(address).unwrap();
+        writeln!(stream, "HELLO {}", PROTOCOL_VERSION + 1).unwrap();
+        assert_eq!(
+            host.join().unwrap(),
+            Err("The other player is running a different version of the game.")
+        );
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(
+            Message::parse(&line),
+            Ok(Message::Hello {
+                version: PROTOCOL_VERSION
+            })
+        );
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert!(line.starts_with("ERROR"));
+
+        // A host that hangs up straight away
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let host = thread::spawn(move || drop(listener.accept().unwrap()));
+        let mut connection = Connection::connect(address).unwrap();
+        host.join().unwrap();
+        assert_eq!(
+            connection.join_handshake(),
+            Err("The other player disconnected.")
+        );
+    }
+}
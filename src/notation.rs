@@ -0,0 +1,425 @@
+//! Game records as text, in the style of chess's PGN, so games can be read and shared.
+//!
+//! A record starts with tags giving the board, the rules and the players, then both fleets
+//! as placed, then every shot by turn. The player fires on odd turns and the opponent on
+//! even ones, and a salvo lists all of its shots after the turn number. Each shot ends with
+//! "-" for a miss, "x" for a hit, or "#" and the name of the ship it sank:
+//!
+//! ```
+//! # use cl_battleship::notation::{parse_record, write_record};
+//! let record = r#"[Size "6x6"]
+//! [Fleet "Destroyer:2, Cruiser:3"]
+//! [Rules "Salvo"]
+//! [Player "You"]
+//! [Opponent "The Admiral"]
+//! [Difficulty "Hard"]
+//! [Result "Player"]
+//!
+//! Player: A1 right, C3 down
+//! Opponent: B5 right, D1 down
+//!
+//! 1. B5x B6#Destroyer 2. A1x 3. D1x E1x 4. C3x 5. F1#Cruiser
+//! "#;
+//! let replay = parse_record(record).unwrap();
+//! assert_eq!(write_record(&replay), record);
+//! ```
+//!
+//! The Rules tag is "Classic" or "Salvo", followed by ", No Touching" when ships may not
+//! touch. A salvo stops at the shot that sinks the last ship, like the fifth turn above.
+//! Spaces in the name of a sunk ship are written as underscores.
+
+use std::fs;
+use std::path::Path;
+
+use super::board::{GameBoard, GridState, ShotOutcome};
+use super::bot::Difficulty;
+use super::engine::{Game, Side};
+use super::replay::Replay;
+use super::rules::{self, Rules};
+use super::ship::{Point, Ship, ShipDirection};
+
+/// The longest line of shots a record is wrapped at
+const LINE_WIDTH: usize = 80;
+
+/// Writes a game out as a record
+pub fn write_record(replay: &Replay) -> String {
+    let fleet: Vec<String> = replay
+        .rules
+        .fleet
+        .iter()
+        .map(|kind| format!("{}:{}", kind.get_name(), kind.get_len()))
+        .collect();
+    let mut rules_name = if replay.rules.salvo {
+        "Salvo"
+    } else {
+        "Classic"
+    }
+    .to_string();
+    if replay.rules.no_touching {
+        rules_name.push_str(", No Touching");
+    }
+    let mut tags = vec![
+        (
+            "Size",
+            format!("{}x{}", replay.rules.width, replay.rules.height),
+        ),
+        ("Fleet", fleet.join(", ")),
+        ("Rules", rules_name),
+    ];
+    if let Some(seed) = replay.seed {
+        tags.push(("Seed", seed.to_string()));
+    }
+    match &replay.hot_seat {
+        Some([player, opponent]) => {
+            tags.push(("Player", player.clone()));
+            tags.push(("Opponent", opponent.clone()));
+        }
+        None => {
+            tags.push(("Player", "You".to_string()));
+            tags.push(("Opponent", "The Admiral".to_string()));
+            tags.push(("Difficulty", replay.difficulty.get_name().to_string()));
+        }
+    }
+    let result = match replay.winner {
+        Some(Side::Player) => "Player",
+        Some(Side::Opponent) => "Opponent",
+        None => "*",
+    };
+    tags.push(("Result", result.to_string()));
+
+    let mut text = String::new();
+    for (name, value) in tags {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        text.push_str(&format!("[{} \"{}\"]\n", name, value));
+    }
+    text.push('\n');
+    for (label, fleet) in ["Player", "Opponent"].iter().zip(&replay.fleets) {
+        let layout: Vec<String> = fleet.iter().map(write_placement).collect();
+        text.push_str(&format!("{}: {}\n", label, layout.join(", ")));
+    }
+    text.push('\n');
+
+    let mut line = String::new();
+    for turn in 1..=replay.turns() {
+        let shots: Vec<String> = replay
+            .shots_on(turn)
+            .iter()
+            .map(|shot| format!("{}{}", shot.target, shot_suffix(&shot.outcome)))
+            .collect();
+        let entry = format!("{}. {}", turn, shots.join(" "));
+        if !line.is_empty() && line.len() + 1 + entry.len() > LINE_WIDTH {
+            text.push_str(&line);
+            text.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&entry);
+    }
+    if !line.is_empty() {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+/// Reads a game from a record. Every shot is fired again, so a record whose outcomes don't
+/// match its fleets is rejected
+pub fn parse_record(text: &str) -> Result<Replay, &'static str> {
+    let mut tags = vec![];
+    let mut layouts = [None, None];
+    let mut shots = String::new();
+    for line in text.lines().map(str::trim) {
+        if let Some(tag) = line.strip_prefix('[') {
+            tags.push(parse_tag(tag)?);
+        } else if let Some(layout) = line.strip_prefix("Player:") {
+            layouts[0] = Some(layout);
+        } else if let Some(layout) = line.strip_prefix("Opponent:") {
+            layouts[1] = Some(layout);
+        } else {
+            shots.push_str(line);
+            shots.push(' ');
+        }
+    }
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let (width, height) = rules::parse_size(tag("Size").ok_or("The record has no board size.")?)?;
+    let fleet = rules::parse_fleet(tag("Fleet").ok_or("The record has no fleet.")?)?;
+    let mut rules = Rules::build(width, height, fleet)?;
+    for name in tag("Rules").unwrap_or("Classic").split(',').map(str::trim) {
+        if name.eq_ignore_ascii_case("Salvo") {
+            rules.salvo = true;
+        } else if name.eq_ignore_ascii_case("No Touching") {
+            rules.no_touching = true;
+        } else if !name.eq_ignore_ascii_case("Classic") {
+            return Err("The rules must be Classic or Salvo, and may add No Touching.");
+        }
+    }
+    let [player, opponent] = layouts.map(|layout| {
+        let layout = layout.ok_or("The record is missing a fleet.")?;
+        parse_layout(layout, &rules)
+    });
+    let mut game = Game::new(
+        rules.clone(),
+        GameBoard::build(player?, &rules),
+        GameBoard::build(opponent?, &rules),
+    );
+
+    let mut turn = 0;
+    let mut volley = vec![];
+    for token in shots.split_whitespace() {
+        if let Some(number) = token.strip_suffix('.') {
+            if turn > 0 {
+                fire(&mut game, &volley)?;
+                volley.clear();
+            }
+            if number.parse() != Ok(turn + 1) {
+                return Err("The turns must be numbered in order from 1.");
+            }
+            turn += 1;
+        } else if turn == 0 {
+            return Err("Shots must follow the number of their turn.");
+        } else {
+            volley.push(parse_shot(token)?);
+        }
+    }
+    if turn > 0 {
+        fire(&mut game, &volley)?;
+    }
+
+    let winner = match tag("Result") {
+        None | Some("*") => None,
+        Some(name) if name.eq_ignore_ascii_case("Player") => Some(Side::Player),
+        Some(name) if name.eq_ignore_ascii_case("Opponent") => Some(Side::Opponent),
+        Some(_) => return Err("The result must be Player, Opponent or *."),
+    };
+    if tag("Result").is_some() && winner != game.winner() {
+        return Err("The result doesn't match the shots.");
+    }
+    let (difficulty, hot_seat) = match tag("Difficulty") {
+        Some(name) => (Difficulty::parse(name)?, None),
+        None => {
+            let name = |tag_name, default: &str| tag(tag_name).unwrap_or(default).to_string();
+            let names = [name("Player", "Player 1"), name("Opponent", "Player 2")];
+            (Difficulty::default(), Some(names))
+        }
+    };
+    let mut replay = Replay::new(&game, difficulty, hot_seat);
+    replay.seed = tag("Seed")
+        .map(|seed| seed.parse().map_err(|_| "The seed must be a number."))
+        .transpose()?;
+    Ok(replay)
+}
+
+/// Writes a game out to a file
+pub fn save_record(path: &Path, replay: &Replay) -> Result<(), &'static str> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|_| "Unable to create the record folder.")?;
+    }
+    fs::write(path, write_record(replay)).map_err(|_| "Unable to write the record.")
+}
+
+/// Reads a game from a file
+pub fn load_record(path: &Path) -> Result<Replay, &'static str> {
+    let text = fs::read_to_string(path).map_err(|_| "Unable to read the record.")?;
+    parse_record(&text)
+}
+
+/// The mark a shot leaves on the board: "-" for a miss and "x" for a hit
+pub fn mark(state: GridState) -> &'static str {
+    match state {
+        GridState::Blank => "",
+        GridState::Hit => "x",
+        GridState::Miss => "-",
+    }
+}
+
+/// What follows a shot's coordinates: its mark, or "#" and the name of the ship it sank
+pub fn shot_suffix(outcome: &ShotOutcome) -> String {
+    match outcome {
+        ShotOutcome::Sunk { kind } | ShotOutcome::FleetDestroyed { kind } => {
+            format!("#{}", kind.get_name().replace(' ', "_"))
+        }
+        ShotOutcome::Hit { .. } => mark(GridState::Hit).to_string(),
+        ShotOutcome::Miss => mark(GridState::Miss).to_string(),
+    }
+}
+
+/// Splits a tag such as `Size "10x10"]`, with the opening bracket already removed
+fn parse_tag(tag: &str) -> Result<(String, String), &'static str> {
+    let (name, value) = tag
+        .strip_suffix(']')
+        .and_then(|tag| tag.split_once(char::is_whitespace))
+        .ok_or("Tags must be written as [Name \"Value\"].")?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or("Tags must be written as [Name \"Value\"].")?;
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => unescaped.extend(chars.next()),
+            char => unescaped.push(char),
+        }
+    }
+    Ok((name.to_string(), unescaped))
+}
+
+/// A ship as its head and the way the rest of it lies, such as "A1 down"
+fn write_placement(ship: &Ship) -> String {
+    // Ships travel head first, so one travelling up lies down from its head
+    let way = match ship.direction {
+        ShipDirection::Up => "down",
+        ShipDirection::Down => "up",
+        ShipDirection::Left => "right",
+        ShipDirection::Right => "left",
+    };
+    format!("{} {}", Point(ship.x, ship.y), way)
+}
+
+/// Reads a fleet placed in the order of the rules' fleet, such as "A1 down, C3 right"
+fn parse_layout(layout: &str, rules: &Rules) -> Result<Vec<Ship>, &'static str> {
+    let placements: Vec<&str> = layout
+        .split(',')
+        .map(str::trim)
+        .filter(|placement| !placement.is_empty())
+        .collect();
+    if placements.len() != rules.fleet.len() {
+        return Err("A fleet doesn't match the ships of the Fleet tag.");
+    }
+    let ships = rules
+        .fleet
+        .iter()
+        .zip(placements)
+        .map(|(kind, placement)| Ship::parse(kind.clone(), placement, rules))
+        .collect::<Result<Vec<Ship>, &'static str>>()?;
+    let points: Vec<&Point> = ships.iter().flat_map(|ship| &ship.points).collect();
+    if (1..points.len()).any(|i| points[..i].contains(&points[i])) {
+        return Err("Two ships of a fleet overlap.");
+    }
+    let touching = |i: usize| ships[..i].iter().any(|ship| ship.does_touch(&ships[i]));
+    if rules.no_touching && (1..ships.len()).any(touching) {
+        return Err("Two ships of a fleet touch.");
+    }
+    Ok(ships)
+}
+
+/// Splits a shot such as "B7x" into its target and its suffix
+fn parse_shot(token: &str) -> Result<(Point, &str), &'static str> {
+    let letters = token
+        .find(|char: char| !char.is_ascii_alphabetic())
+        .unwrap_or(token.len());
+    let end = token[letters..]
+        .find(|char: char| !char.is_ascii_digit())
+        .map_or(token.len(), |digits| letters + digits);
+    let (coordinates, suffix) = token.split_at(end);
+    if suffix.is_empty() {
+        return Err("Every shot needs an outcome, such as \"B7x\".");
+    }
+    Ok((Point::parse(coordinates)?, suffix))
+}
+
+/// Fires a turn's shots, checking each outcome is the one the record gives
+fn fire(game: &mut Game, volley: &[(Point, &str)]) -> Result<(), &'static str> {
+    if volley.is_empty() {
+        return Err("Every turn needs at least one shot.");
+    }
+    let targets: Vec<Point> = volley.iter().map(|(target, _)| target.clone()).collect();
+    let outcomes = game.fire_recorded(&targets)?;
+    let matches = volley
+        .iter()
+        .zip(&outcomes)
+        .all(|((_, suffix), outcome)| shot_suffix(outcome).eq_ignore_ascii_case(suffix));
+    if !matches {
+        return Err("A shot's outcome doesn't match the fleets.");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::generate_game_board;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    const RECORD: &str = r#"[Size "6x6"]
+[Fleet "Patrol Boat:2, Sub:3"]
+[Rules "Classic"]
+[Player "Ann \"The Ace\""]
+[Opponent "Bo"]
+[Result "Player"]
+
+Player: A1 right, C1 down
+Opponent: F5 right, C6 up
+
+1. F5x 2. A1x 3. F6#Patrol_Boat 4. a2#patrol_boat
+5. A6x 6. C1x 7. B6x 8. F1- 9. C6#Sub
+"#;
+
+    #[test]
+    fn records_are_written_and_read() {
+        let replay = parse_record(RECORD).unwrap();
+        assert_eq!(replay.winner, Some(Side::Player));
+        assert_eq!(replay.turns(), 9);
+        assert_eq!(
+            replay.hot_seat,
+            Some(["Ann \"The Ace\"".to_string(), "Bo".to_string()])
+        );
+        assert_eq!(replay.fleets[1][1].points[2], Point(5, 2));
+        assert_eq!(replay.shots[3].shooter, Side::Opponent);
+        assert_eq!(replay.shots[3].target, Point(1, 0));
+
+        // Outcomes that don't match the fleets, and shots after the end, are refused
+        let wrong = RECORD.replace("F1-", "F1x");
+        assert_eq!(
+            parse_record(&wrong).err(),
+            Some("A shot's outcome doesn't match the fleets.")
+        );
+        let late = RECORD.replace("C6#Sub", "C6#Sub 10. F1-");
+        assert_eq!(parse_record(&late).err(), Some("The game is over."));
+
+        // A whole salvo game against the Admiral, without touching ships, survives being
+        // written and read
+        let rules = Rules {
+            salvo: true,
+            no_touching: true,
+            ..Rules::default()
+        };
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut game = Game::new(
+            rules.clone(),
+            generate_game_board(&rules, &mut rng),
+            generate_game_board(&rules, &mut rng),
+        );
+        // Each side sweeps the cells left open, row by row
+        while !game.is_game_over() {
+            let target = &game.board(game.turn().other()).board;
+            let volley: Vec<Point> = (0..rules.height)
+                .flat_map(|y| (0..rules.width).map(move |x| Point(x, y)))
+                .filter(|point| target[point.1 as usize][point.0 as usize] == GridState::Blank)
+                .take(game.shots_per_turn())
+                .collect();
+            game.fire_volley(&volley).unwrap();
+        }
+        let mut replay = Replay::new(&game, Difficulty::Hard, None);
+        replay.seed = Some(5);
+        let text = write_record(&replay);
+        assert!(text.contains("[Rules \"Salvo, No Touching\"]"));
+        assert!(text.lines().all(|line| line.len() <= LINE_WIDTH));
+        let read = parse_record(&text).unwrap();
+        assert_eq!(read.shots, replay.shots);
+        assert_eq!(read.winner, replay.winner);
+        assert_eq!(
+            (read.difficulty, &read.hot_seat, read.seed),
+            (Difficulty::Hard, &None, Some(5))
+        );
+        assert_eq!(write_record(&read), text);
+    }
+}
@@ -0,0 +1,210 @@
+use cl_battleship::{
+    board::GameBoard,
+    engine::Side,
+    fair_play::Verdict,
+    net::{self, Connection, DEFAULT_PORT, RemoteGame},
+    rules::Rules,
+    ship::Point,
+};
+use console::{Term, style};
+
+use crate::{
+    create_board,
+    game::{Announcer, Crosshair, TargetInput, colour_grid, get_target, render_grids},
+    mouse,
+};
+
+/// Hosts a game on the port, and plays it once another player joins
+pub fn host(rules: Rules, port: u16) {
+    let term = Term::buffered_stdout();
+    let result = net::listen(port).and_then(|listener| {
+        term.write_line(&format!(
+            "Waiting for a player to join on port {}...",
+            style(port).bold()
+        ));
+        term.flush();
+        let mut connection = Connection::accept(&listener)?;
+        term.clear_last_lines(1);
+        connection.host_handshake(&rules)?;
+        play(&term, connection, rules, true)
+    });
+    if let Err(error) = result {
+        report(&term, error);
+    }
+}
+
+/// Joins a game hosted at the address, such as "192.168.1.20" or "192.168.1.20:7373"
+pub fn join(address: &str) {
+    let term = Term::buffered_stdout();
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    };
+    term.write_line(&format!("Connecting to {}...", style(&address).bold()));
+    term.flush();
+    let result = Connection::connect(address.as_str()).and_then(|mut connection| {
+        term.clear_last_lines(1);
+        let rules = connection.join_handshake()?;
+        play(&term, connection, rules, false)
+    });
+    if let Err(error) = result {
+        report(&term, error);
+    }
+}
+
+/// Places the player's fleet, then plays turns until a fleet is destroyed
+fn play(
+    term: &Term,
+    connection: Connection,
+    rules: Rules,
+    is_host: bool,
+) -> Result<(), &'static str> {
+    let own_board = create_board::start(&rules);
+    term.write_line("Waiting for the other player to place their fleet...");
+    term.flush();
+    let mut game = RemoteGame::start(connection, rules, own_board, is_host)?;
+    term.clear_last_lines(1);
+    mouse::anchor(term);
+    render(term, &game, None);
+
+    let screen_lines = 2 * game.rules().height as usize + 6;
+    let mut cursor = Point(0, 0);
+    loop {
+        let shooter = game.turn();
+        let (targets, outcomes) = if shooter == Side::Player {
+            let shots = game.shots_per_turn();
+            let draw = |term: &Term, crosshair: &Crosshair| render(term, &game, Some(crosshair));
+            let input = get_target(
+                term,
+                &game.target,
+                shots,
+                &mut cursor,
+                &mut false,
+                true,
+                &draw,
+            );
+            let targets = match input {
+                TargetInput::Fire(targets) => targets,
+                TargetInput::Leave => {
+                    game.leave();
+                    term.clear_last_lines(screen_lines);
+                    term.write_line(&format!(
+                        "{} You left the game.",
+                        style(" Left ").on_black().bold()
+                    ));
+                    term.flush();
+                    return Ok(());
+                }
+                TargetInput::Save | TargetInput::Load => {
+                    unreachable!("Online games don't offer to save or load")
+                }
+            };
+            let outcomes = game.fire(&targets)?;
+            (targets, outcomes)
+        } else {
+            term.write_line("Waiting for the other player to fire...");
+            term.flush();
+            let turn = game.receive_fire()?;
+            term.clear_last_lines(1);
+            turn
+        };
+
+        let message = announcer(shooter).describe_turn(&targets, &outcomes);
+        term.clear_last_lines(screen_lines);
+        term.write_line(&message);
+        if let Some(reason) = game.caught() {
+            term.write_line(&format!(
+                "{} Your opponent cheated! {}",
+                style(" Foul ").on_red().bold(),
+                reason
+            ));
+            render(term, &game, None);
+            term.flush();
+            return Ok(());
+        }
+        if let Some(winner) = game.winner() {
+            term.write_line(&if winner == Side::Player {
+                format!("{} You won!", style("  Win ").on_yellow().bold())
+            } else {
+                format!("{} You lose!", style(" Loss ").on_black().bold())
+            });
+            // Check the other player's replies now their fleet can be seen. An opponent
+            // who never shows their fleet can't be trusted any more than one who lied
+            match game.reveal() {
+                Ok(Verdict::Fair(revealed)) => {
+                    term.write_line(&format!(
+                        "{} Every reply from your opponent matched their fleet.",
+                        style(" Fair ").on_blue().bold()
+                    ));
+                    render_revealed(term, &game, &revealed);
+                }
+                Ok(Verdict::Cheated(reason)) => {
+                    term.write_line(&format!(
+                        "{} Your opponent cheated! {}",
+                        style(" Foul ").on_red().bold(),
+                        reason
+                    ));
+                    render(term, &game, None);
+                }
+                Err(error) => {
+                    term.write_line(&format!(
+                        "{} Your opponent's replies could not be checked. {}",
+                        style(" Foul ").on_red().bold(),
+                        error
+                    ));
+                    render(term, &game, None);
+                }
+            }
+            term.flush();
+            return Ok(());
+        }
+        render(term, &game, None);
+        term.flush();
+    }
+}
+
+/// How the shots of each side are announced
+fn announcer(shooter: Side) -> Announcer {
+    match shooter {
+        Side::Player => Announcer {
+            name: "You".to_string(),
+            owner: "the".to_string(),
+            hostile: false,
+        },
+        Side::Opponent => Announcer {
+            name: "Your opponent".to_string(),
+            owner: "your".to_string(),
+            hostile: true,
+        },
+    }
+}
+
+/// Render the grids, with the crosshair if the player is aiming.
+/// Only the shots fired at the other player's fleet are known
+fn render(term: &Term, game: &RemoteGame, crosshair: Option<&Crosshair>) {
+    let target_grid = colour_grid(&game.target.board, &[], crosshair);
+    render_grids(term, game.rules(), target_grid, own_grid(game));
+}
+
+/// Render the grids, showing the other player's fleet as they revealed it
+fn render_revealed(term: &Term, game: &RemoteGame, revealed: &GameBoard) {
+    let target_grid = colour_grid(&revealed.board, &ship_points(revealed), None);
+    render_grids(term, game.rules(), target_grid, own_grid(game));
+}
+
+/// The player's own fleet and the shots fired at it
+fn own_grid(game: &RemoteGame) -> String {
+    colour_grid(&game.own_board.board, &ship_points(&game.own_board), None)
+}
+
+/// Every cell covered by a ship
+fn ship_points(board: &GameBoard) -> Vec<&Point> {
+    board.ships.iter().flat_map(|ship| &ship.points).collect()
+}
+
+/// Shows why the online game ended early
+fn report(term: &Term, error: &str) {
+    term.write_line(&format!("{} {}", style(" Error ").on_red().bold(), error));
+    term.flush();
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::Path;
+
+use super::board::{GameBoard, rebuild_fleet};
+use super::bot::Difficulty;
+use super::engine::{Game, ShotRecord, Side};
+use super::rules::Rules;
+use super::ship::Ship;
+use serde::{Deserialize, Serialize};
+
+/// The version of the replay format written by this build
+pub const REPLAY_VERSION: u32 = 1;
+
+/// A finished game, kept so it can be watched again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    version: u32,
+    pub rules: Rules,
+    /// The player's fleet, then the opponent's, as they were placed
+    pub fleets: [Vec<Ship>; 2],
+    pub shots: Vec<ShotRecord>,
+    pub winner: Option<Side>,
+    pub difficulty: Difficulty,
+    /// The names of both players in a hot-seat game, or `None` against the Admiral
+    pub hot_seat: Option<[String; 2]>,
+    /// The seed the game was played with, if it is known
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+impl Replay {
+    /// Keeps a game's fleets and shots
+    pub fn new(game: &Game, difficulty: Difficulty, hot_seat: Option<[String; 2]>) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            rules: game.rules().clone(),
+            fleets: [Side::Player, Side::Opponent].map(|side| game.board(side).ships.clone()),
+            shots: game.history().to_vec(),
+            winner: game.winner(),
+            difficulty,
+            hot_seat,
+            seed: None,
+        }
+    }
+
+    /// The number of turns played
+    pub fn turns(&self) -> u32 {
+        self.shots.last().map_or(0, |shot| shot.turn)
+    }
+
+    /// The shots fired on a turn
+    pub fn shots_on(&self, turn: u32) -> Vec<&ShotRecord> {
+        self.shots.iter().filter(|shot| shot.turn == turn).collect()
+    }
+
+    /// The player's board, then the opponent's, once `turn` turns have been played
+    pub fn boards_at(&self, turn: u32) -> Result<[GameBoard; 2], &'static str> {
+        let mut boards = [
+            build_board(&self.rules, &self.fleets[0])?,
+            build_board(&self.rules, &self.fleets[1])?,
+        ];
+        let played = self
+            .shots
+            .iter()
+            .take_while(|shot| shot.turn <= turn)
+            .count();
+        for volley in self.shots[..played].chunk_by(|a, b| a.turn == b.turn) {
+            let target = match volley[0].shooter {
+                Side::Player => &mut boards[1],
+                Side::Opponent => &mut boards[0],
+            };
+            for shot in volley {
+                if target.strike(&shot.target).as_ref() != Ok(&shot.outcome) {
+                    return Err("The replay doesn't match its fleets.");
+                }
+            }
+            // The water around sunk ships is marked once the whole volley has landed
+            if self.rules.no_touching {
+                for shot in volley.iter().filter(|shot| shot.outcome.is_sunk()) {
+                    target.mark_around_sunk(&shot.target);
+                }
+            }
+        }
+        Ok(boards)
+    }
+
+    /// Check a replay read back follows its rules, and every shot can be played again
+    fn check(&self) -> Result<(), &'static str> {
+        self.rules.check()?;
+        self.boards_at(self.turns()).map(|_| ())
+    }
+}
+
+/// Places the ships of a fleet again, without the hits they took
+fn build_board(rules: &Rules, fleet: &[Ship]) -> Result<GameBoard, &'static str> {
+    Ok(GameBoard::build(rebuild_fleet(rules, fleet)?, rules))
+}
+
+/// Writes a replay to disk
+pub fn save_replay(path: &Path, replay: &Replay) -> Result<(), &'static str> {
+    let text = serde_json::to_string(replay).map_err(|_| "Unable to serialise the replay.")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|_| "Unable to create the replay folder.")?;
+    }
+    fs::write(path, text).map_err(|_| "Unable to write the replay.")
+}
+
+/// Reads a replay from disk
+pub fn load_replay(path: &Path) -> Result<Replay, &'static str> {
+    let text = fs::read_to_string(path).map_err(|_| "Unable to read the replay.")?;
+    let replay: Replay = serde_json::from_str(&text).map_err(|_| "The replay is corrupted.")?;
+    if replay.version > REPLAY_VERSION {
+        return Err("The replay is from a newer version of the game.");
+    }
+    replay.check().map_err(|_| "The replay is corrupted.")?;
+    Ok(replay)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::{GridState, generate_game_board};
+    use crate::ship::Point;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn replays_rebuild_every_turn() {
+        let rules = Rules::default();
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut game = Game::new(
+            rules.clone(),
+            generate_game_board(&rules, &mut rng),
+            generate_game_board(&rules, &mut rng),
+        );
+        // Sweep the board row by row until one side wins
+        let mut targets = (0..rules.height).flat_map(|y| (0..rules.width).map(move |x| (x, y)));
+        let mut turns = 0;
+        while !game.is_game_over() {
+            let (x, y) = targets.next().unwrap();
+            game.fire(Point(x, y)).unwrap();
+            if !game.is_game_over() {
+                game.fire(Point(x, y)).unwrap();
+            }
+            turns += 1;
+        }
+
+        let replay = Replay::new(&game, Difficulty::Hard, None);
+        let text = serde_json::to_string(&replay).unwrap();
+        let replay: Replay = serde_json::from_str(&text).unwrap();
+        assert_eq!(replay.winner, game.winner());
+        assert!(replay.turns() >= turns);
+
+        // Nothing has been fired at the start, and the end matches the finished game
+        let [player, opponent] = replay.boards_at(0).unwrap();
+        assert!(
+            player
+                .board
+                .iter()
+                .flatten()
+                .all(|cell| *cell == GridState::Blank)
+        );
+        assert_eq!(opponent.ships_left, rules.fleet.len() as u8);
+        let [player, opponent] = replay.boards_at(replay.turns()).unwrap();
+        assert_eq!(player.board, game.player_board.board);
+        assert_eq!(opponent.board, game.opponent_board.board);
+
+        // The first turn is the player's single shot
+        let first = replay.shots_on(1);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].shooter, Side::Player);
+        let [_, opponent] = replay.boards_at(1).unwrap();
+        assert_eq!(opponent.board[0][0], game.opponent_board.board[0][0]);
+
+        // Replays that break their rules are refused
+        assert_eq!(replay.check(), Ok(()));
+        let mut damaged = replay.clone();
+        damaged.fleets[0][1] = damaged.fleets[0][0].clone();
+        assert_eq!(damaged.check(), Err("Wrong kind of ship."));
+        let mut damaged = replay.clone();
+        damaged.shots[0].target = Point(10, 0);
+        assert!(damaged.check().is_err());
+        let mut damaged = replay;
+        damaged.rules.width = 200;
+        assert_eq!(damaged.check(), Err("Board is too large."));
+    }
+}
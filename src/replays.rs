@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+
+use cl_battleship::notation;
+use cl_battleship::replay::{self, Replay};
+use cl_battleship::ship::Point;
+use console::{Key, Term, style};
+
+use crate::{
+    files,
+    game::{Opponent, generate_grid, render_grids},
+    menu::highlight,
+    terminal_utils::print_center,
+};
+
+/// How many of the latest replays are offered
+const LISTED: usize = 10;
+
+/// Lets the player pick one of the latest finished games to watch, or import a record
+pub fn choose(term: &Term) {
+    let replays = latest_replays();
+    let import = replays.len();
+    let mut selected = 0;
+    loop {
+        term.write_line("");
+        if replays.is_empty() {
+            print_center(term, "No games have been finished yet.");
+        }
+        replays.iter().enumerate().for_each(|(i, replay)| {
+            let label = describe(replay);
+            print_center(term, &highlight(&label, i == selected));
+        });
+        print_center(term, &highlight("Import a Game Record", selected == import));
+        print_center(term, &highlight("Back", selected == import + 1));
+        term.flush();
+        let key = term.read_key();
+        term.clear_last_lines(replays.len().max(1) + 3);
+        match key {
+            Ok(Key::ArrowUp) | Ok(Key::Char('w')) | Ok(Key::Char('W')) => {
+                selected = selected.saturating_sub(1)
+            }
+            Ok(Key::ArrowDown) | Ok(Key::Char('s')) | Ok(Key::Char('S')) => {
+                selected = (selected + 1).min(import + 1)
+            }
+            Ok(Key::Enter) | Ok(Key::Char(' ')) if selected < import => {
+                return watch(&replays[selected]);
+            }
+            Ok(Key::Enter) | Ok(Key::Char(' ')) if selected == import => {
+                if let Some(replay) = import_record(term) {
+                    return watch(&replay);
+                }
+            }
+            Ok(Key::Enter) | Ok(Key::Char(' ')) | Ok(Key::Escape) | Err(_) => return,
+            _ => (),
+        }
+    }
+}
+
+/// Asks for the file of a game record and reads it
+fn import_record(term: &Term) -> Option<Replay> {
+    term.write_line("");
+    print_center(term, "Type the path of the game record to import:");
+    term.flush();
+    let path = term.read_line().unwrap_or_default();
+    term.clear_last_lines(3);
+    let path = path.trim();
+    if path.is_empty() {
+        return None;
+    }
+    match notation::load_record(Path::new(path)) {
+        Ok(replay) => Some(replay),
+        Err(error) => {
+            term.write_line("");
+            print_center(
+                term,
+                &format!("{} {}", style(" Error ").on_red().bold(), error),
+            );
+            term.flush();
+            term.read_key().ok();
+            term.clear_last_lines(2);
+            None
+        }
+    }
+}
+
+/// Keeps the replay of a game that just finished, deleting the oldest so no more are kept
+/// than are offered
+pub fn keep(replay: &Replay) {
+    replay::save_replay(&files::new_replay_path(), replay).ok();
+    for path in replay_paths().into_iter().skip(LISTED) {
+        std::fs::remove_file(path).ok();
+    }
+}
+
+/// The latest replays that can be read, newest first
+fn latest_replays() -> Vec<Replay> {
+    replay_paths()
+        .into_iter()
+        .filter_map(|path| replay::load_replay(&path).ok())
+        .take(LISTED)
+        .collect()
+}
+
+/// Every replay file kept, newest first
+fn replay_paths() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(files::replays_dir()) else {
+        return vec![];
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    paths.sort();
+    paths.reverse();
+    paths
+}
+
+/// Who played, and how the game ended
+fn describe(replay: &Replay) -> String {
+    let opponent = opponent(replay);
+    let players = match &replay.hot_seat {
+        Some(names) => format!("{} against {}", names[0], names[1]),
+        None => format!("Against the Admiral ({})", replay.difficulty.get_name()),
+    };
+    match replay.winner {
+        Some(winner) => format!(
+            "{}: {} won on turn {}",
+            players,
+            opponent.name(winner),
+            replay.turns()
+        ),
+        None => format!("{}: unfinished", players),
+    }
+}
+
+/// Steps through a finished game, turn by turn
+pub fn watch(replay: &Replay) {
+    let term = Term::buffered_stdout();
+    let opponent = opponent(replay);
+    // The grids and the help, below the turn and its message
+    let grid_lines = 2 * replay.rules.height as usize + 7;
+    let last_turn = replay.turns();
+    let mut turn = 0;
+    // Replaces the help for a turn, after the game is exported
+    let mut notice = None;
+    term.clear_screen().ok();
+    loop {
+        let [player_board, opponent_board] = match replay.boards_at(turn) {
+            Ok(boards) => boards,
+            Err(error) => {
+                term.write_line(&format!("{} {}", style(" Error ").on_red().bold(), error));
+                term.flush();
+                term.read_key().ok();
+                return;
+            }
+        };
+        let shots = replay.shots_on(turn);
+        let message = match shots.first() {
+            None => "The fleets before the first shot.".to_string(),
+            Some(first) => {
+                let targets: Vec<Point> = shots.iter().map(|shot| shot.target.clone()).collect();
+                let outcomes: Vec<_> = shots.iter().map(|shot| shot.outcome.clone()).collect();
+                opponent
+                    .announcer(first.shooter)
+                    .describe_turn(&targets, &outcomes)
+            }
+        };
+        term.write_line(&format!(
+            "{} Turn {} of {}",
+            style(" Replay ").on_blue().bold(),
+            turn,
+            last_turn
+        ));
+        term.write_line(&message);
+        render_grids(
+            &term,
+            &replay.rules,
+            generate_grid(&opponent_board, true, None),
+            generate_grid(&player_board, true, None),
+        );
+        term.write_line(&notice.take().unwrap_or_else(|| {
+            format!(
+                "Step with the {} keys, jump with {} and {}, {} to export, {} to leave",
+                style("Arrow").bold(),
+                style("Home").bold(),
+                style("End").bold(),
+                style("X").bold(),
+                style("Esc").bold()
+            )
+        }));
+        term.flush();
+        let key = term.read_key();
+        term.clear_last_lines(grid_lines + 1 + message.lines().count());
+        match key {
+            Ok(Key::ArrowLeft) | Ok(Key::ArrowUp) | Ok(Key::Char('a')) | Ok(Key::Char('A')) => {
+                turn = turn.saturating_sub(1)
+            }
+            Ok(Key::ArrowRight)
+            | Ok(Key::ArrowDown)
+            | Ok(Key::Char('d'))
+            | Ok(Key::Char('D'))
+            | Ok(Key::Char(' '))
+            | Ok(Key::Enter) => turn = (turn + 1).min(last_turn),
+            Ok(Key::Home) => turn = 0,
+            Ok(Key::End) => turn = last_turn,
+            Ok(Key::Char('x')) | Ok(Key::Char('X')) => notice = Some(export(replay)),
+            Ok(Key::Escape) | Ok(Key::Char('q')) | Ok(Key::Char('Q')) | Err(_) => break,
+            _ => (),
+        }
+    }
+    term.clear_screen().ok();
+    term.flush();
+}
+
+/// Writes the game out as a record, and says where it went
+fn export(replay: &Replay) -> String {
+    let path = files::new_record_path();
+    match notation::save_record(&path, replay) {
+        Ok(()) => format!("The game was exported to {}", path.display()),
+        Err(error) => format!("{} {}", style(" Error ").on_red().bold(), error),
+    }
+}
+
+/// Who the replay's player was up against, for naming the sides
+fn opponent(replay: &Replay) -> Opponent {
+    match &replay.hot_seat {
+        Some(names) => Opponent::HotSeat(names.clone()),
+        None => Opponent::Admiral(replay.difficulty),
+    }
+}
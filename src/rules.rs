@@ -0,0 +1,168 @@
+use super::ship::ShipKind;
+use serde::{Deserialize, Serialize};
+
+/// The smallest board that can be played on
+pub const MIN_SIZE: u8 = 5;
+/// The largest board that can be labelled and rendered
+pub const MAX_SIZE: u8 = 100;
+
+/// Settings that change how a game is played
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rules {
+    pub width: u8,
+    pub height: u8,
+    /// The ships each side places, in placement order
+    pub fleet: Vec<ShipKind>,
+    /// Each side fires one shot for every ship it has afloat, instead of one shot a turn
+    #[serde(default)]
+    pub salvo: bool,
+    /// Ships may not touch, even diagonally, and the water around a sunk ship is marked
+    #[serde(default)]
+    pub no_touching: bool,
+}
+
+impl Rules {
+    /// Try to create rules for a board of the given size and fleet
+    pub fn build(width: u8, height: u8, fleet: Vec<ShipKind>) -> Result<Self, &'static str> {
+        if width < MIN_SIZE || height < MIN_SIZE {
+            return Err("Board is too small.");
+        }
+        if width > MAX_SIZE || height > MAX_SIZE {
+            return Err("Board is too large.");
+        }
+        if fleet.is_empty() {
+            return Err("The fleet has no ships.");
+        }
+        if fleet.len() > u8::MAX as usize {
+            return Err("The fleet has too many ships.");
+        }
+        if fleet.iter().any(|kind| kind.get_len() > width.max(height)) {
+            return Err("A ship is too long for the board.");
+        }
+        // Keep enough open water for a random layout to always be found
+        let fleet_cells: usize = fleet.iter().map(|kind| kind.get_len() as usize).sum();
+        if fleet_cells * 2 > width as usize * height as usize {
+            return Err("The fleet is too large for the board.");
+        }
+        Ok(Self {
+            width,
+            height,
+            fleet,
+            salvo: false,
+            no_touching: false,
+        })
+    }
+
+    /// Check rules read back, such as from a file or the network, are ones `build` could give
+    pub fn check(&self) -> Result<(), &'static str> {
+        Rules::build(self.width, self.height, self.fleet.clone())?;
+        if self.no_touching {
+            self.check_no_touching()?;
+        }
+        Ok(())
+    }
+
+    /// Check the fleet leaves enough room to be placed without any ships touching
+    pub fn check_no_touching(&self) -> Result<(), &'static str> {
+        // A ship and the water along one side and one end of it fit on a board one cell
+        // larger each way, and twice that room is kept like the fleet size check does
+        let cells: usize = self
+            .fleet
+            .iter()
+            .map(|kind| 2 * (kind.get_len() as usize + 1))
+            .sum();
+        if cells * 2 > (self.width as usize + 1) * (self.height as usize + 1) {
+            return Err("The fleet is too large to keep the ships apart.");
+        }
+        Ok(())
+    }
+}
+
+impl Default for Rules {
+    /// The classic 10x10 game
+    fn default() -> Self {
+        Self {
+            width: 10,
+            height: 10,
+            fleet: standard_fleet(),
+            salvo: false,
+            no_touching: false,
+        }
+    }
+}
+
+/// The five ships of the classic game
+pub fn standard_fleet() -> Vec<ShipKind> {
+    [
+        ("Carrier", 5),
+        ("Battleship", 4),
+        ("Cruiser", 3),
+        ("Submarine", 3),
+        ("Destroyer", 2),
+    ]
+    .iter()
+    .map(|(name, len)| ShipKind::build(name, *len).expect("Standard ships are valid"))
+    .collect()
+}
+
+/// Parses a board size such as "15x15", or "8" for a square board
+pub fn parse_size(size: &str) -> Result<(u8, u8), &'static str> {
+    let (width, height) = size.split_once(['x', 'X']).unwrap_or((size, size));
+    let width: u8 = width.trim().parse().map_err(|_| "Invalid board size.")?;
+    let height: u8 = height.trim().parse().map_err(|_| "Invalid board size.")?;
+    Ok((width, height))
+}
+
+/// Parses a fleet such as "Dreadnought:6, Destroyer:2, Destroyer:2"
+pub fn parse_fleet(fleet: &str) -> Result<Vec<ShipKind>, &'static str> {
+    fleet
+        .split(',')
+        .map(|ship| {
+            let (name, len) = ship.split_once(':').ok_or("Ships must be written as Name:Length.")?;
+            let len: u8 = len.trim().parse().map_err(|_| "Ship lengths must be numbers.")?;
+            ShipKind::build(name, len)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fleets_are_parsed_and_checked() {
+        let fleet = parse_fleet("Dreadnought:6, Destroyer:2,Destroyer : 2").unwrap();
+        assert_eq!(fleet.len(), 3);
+        assert_eq!(fleet[0].get_name(), "Dreadnought");
+        assert_eq!(fleet[0].get_len(), 6);
+        assert_eq!(fleet[1], fleet[2]);
+
+        assert_eq!(parse_fleet("Carrier"), Err("Ships must be written as Name:Length."));
+        assert_eq!(parse_fleet("Carrier:five"), Err("Ship lengths must be numbers."));
+        assert_eq!(parse_fleet("Raft:0"), Err("Ships must be at least one long."));
+        assert_eq!(parse_fleet(":3"), Err("Ship names can't be empty."));
+
+        assert_eq!(
+            Rules::build(8, 8, parse_fleet("Dreadnought:9").unwrap()),
+            Err("A ship is too long for the board.")
+        );
+        assert_eq!(
+            Rules::build(5, 5, standard_fleet()),
+            Err("The fleet is too large for the board.")
+        );
+        assert!(Rules::build(8, 8, standard_fleet()).is_ok());
+
+        // Keeping ships apart needs more room
+        assert_eq!(Rules::default().check_no_touching(), Ok(()));
+        assert_eq!(
+            Rules::build(8, 8, standard_fleet())
+                .unwrap()
+                .check_no_touching(),
+            Err("The fleet is too large to keep the ships apart.")
+        );
+
+        assert_eq!(parse_size("15x12"), Ok((15, 12)));
+        assert_eq!(parse_size("8"), Ok((8, 8)));
+        assert_eq!(parse_size("big"), Err("Invalid board size."));
+    }
+}
@@ -0,0 +1,284 @@
+use std::fs;
+use std::path::Path;
+
+use super::bot::Difficulty;
+use super::engine::Game;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The version of the save format written by this build.
+/// Bump it when the format changes, and teach `upgrade` how to read the old one.
+pub const SAVE_VERSION: u32 = 1;
+
+/// The contents of a save file
+#[derive(Serialize, Deserialize)]
+pub struct SaveFile {
+    version: u32,
+    pub game: Game,
+    /// Fields added within a version need a default, so older saves still load
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// The names of both players in a hot-seat game, or `None` against the Admiral
+    #[serde(default)]
+    pub hot_seat: Option<[String; 2]>,
+    /// Turns on which the player has asked for a hint so far
+    #[serde(default)]
+    pub hints: u32,
+    /// The Admiral's random numbers, so a resumed game carries on as it would have.
+    /// Saves from before they were kept have none
+    #[serde(default)]
+    pub rng: Option<SeededRng>,
+}
+
+impl SaveFile {
+    /// Create a save of the current version
+    pub fn new(game: Game, difficulty: Difficulty) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            game,
+            difficulty,
+            hot_seat: None,
+            hints: 0,
+            rng: None,
+        }
+    }
+}
+
+/// Random numbers drawn from a seed, which keep track of how far along they are so they can be
+/// saved and picked up again from the same place
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "SavedRng", into = "SavedRng")]
+pub struct SeededRng {
+    seed: u64,
+    rng: ChaCha12Rng,
+}
+
+/// How a `SeededRng` is saved
+#[derive(Serialize, Deserialize)]
+struct SavedRng {
+    seed: u64,
+    /// The 32-bit words drawn so far
+    words: u64,
+}
+
+impl SeededRng {
+    /// Create random numbers from the start of a seed
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed the numbers are drawn from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl From<SavedRng> for SeededRng {
+    /// Skips straight to where the numbers had got to when they were saved
+    fn from(saved: SavedRng) -> Self {
+        let mut rng = Self::new(saved.seed);
+        rng.rng.set_word_pos(saved.words as u128);
+        rng
+    }
+}
+
+impl From<SeededRng> for SavedRng {
+    fn from(rng: SeededRng) -> Self {
+        Self {
+            seed: rng.seed,
+            words: rng.rng.get_word_pos() as u64,
+        }
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+}
+
+/// Writes a game in progress to disk
+pub fn save_game(path: &Path, save: &SaveFile) -> Result<(), &'static str> {
+    let text = to_string(save)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|_| "Unable to create the save folder.")?;
+    }
+    fs::write(path, text).map_err(|_| "Unable to write the save file.")
+}
+
+/// Reads a game in progress from disk
+pub fn load_game(path: &Path) -> Result<SaveFile, &'static str> {
+    let text = fs::read_to_string(path).map_err(|_| "Unable to read the save file.")?;
+    from_str(&text)
+}
+
+/// Serialises a game into the save format
+pub fn to_string(save: &SaveFile) -> Result<String, &'static str> {
+    serde_json::to_string_pretty(save).map_err(|_| "Unable to serialise the game.")
+}
+
+/// Deserialises a game written by this or any earlier version
+pub fn from_str(text: &str) -> Result<SaveFile, &'static str> {
+    let mut save: Value = serde_json::from_str(text).map_err(|_| "The save file is corrupted.")?;
+    let version = save
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or("The save file has no version.")?;
+    if version > SAVE_VERSION as u64 {
+        return Err("The save file is from a newer version of the game.");
+    }
+    upgrade(&mut save, version as u32)?;
+    let mut save: SaveFile =
+        serde_json::from_value(save).map_err(|_| "The save file is corrupted.")?;
+    // An edited or damaged game is refused here, rather than breaking play later
+    save.game = save
+        .game
+        .rebuild()
+        .map_err(|_| "The save file is corrupted.")?;
+    Ok(save)
+}
+
+/// Migrates an older save, one version at a time, to the current format
+fn upgrade(_save: &mut Value, version: u32) -> Result<(), &'static str> {
+    match version {
+        SAVE_VERSION => Ok(()),
+        _ => Err("The save file is from an unsupported version of the game."),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::generate_game_board;
+    use crate::rules::Rules;
+    use crate::ship::Point;
+
+    #[test]
+    fn games_survive_a_round_trip() {
+        let rules = Rules::build(12, 8, Rules::default().fleet).unwrap();
+        let mut game = Game::new(
+            rules.clone(),
+            generate_game_board(&rules, &mut rand::rng()),
+            generate_game_board(&rules, &mut rand::rng()),
+        );
+        game.fire(Point(3, 4)).unwrap();
+
+        let mut save = SaveFile::new(game.clone(), Difficulty::Hard);
+        save.hot_seat = Some(["Ada".to_string(), "Grace".to_string()]);
+        let loaded = from_str(&to_string(&save).unwrap()).unwrap();
+        assert_eq!(loaded.difficulty, Difficulty::Hard);
+        assert_eq!(loaded.hot_seat, save.hot_seat);
+        let loaded = loaded.game;
+        assert_eq!(loaded.rules(), &rules);
+        assert_eq!(loaded.turn(), game.turn());
+        assert_eq!(loaded.opponent_board.board, game.opponent_board.board);
+        assert_eq!(loaded.player_board.ships.len(), rules.fleet.len());
+    }
+
+    #[test]
+    fn random_numbers_carry_on_after_a_save() {
+        let mut rng = SeededRng::new(8);
+        let mut bytes = [0; 7];
+        for _ in 0..40 {
+            rng.next_u32();
+            rng.next_u64();
+            rng.fill_bytes(&mut bytes);
+        }
+        let text = serde_json::to_string(&rng).unwrap();
+        let mut loaded: SeededRng = serde_json::from_str(&text).unwrap();
+        assert_eq!(loaded.seed(), 8);
+        for _ in 0..100 {
+            assert_eq!(loaded.next_u64(), rng.next_u64());
+            assert_eq!(loaded.next_u32(), rng.next_u32());
+        }
+
+        // However far along a damaged save claims to be, it is picked up at once
+        let far: SeededRng =
+            serde_json::from_str(r#"{"seed":8,"words":18446744073709551615}"#).unwrap();
+        assert_eq!(far.seed(), 8);
+    }
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        let rules = Rules::default();
+        let game = Game::new(
+            rules.clone(),
+            generate_game_board(&rules, &mut rand::rng()),
+            generate_game_board(&rules, &mut rand::rng()),
+        );
+        let mut save = serde_json::to_value(SaveFile::new(game, Difficulty::Expert)).unwrap();
+        save.as_object_mut().unwrap().remove("difficulty");
+        save.as_object_mut().unwrap().remove("hot_seat");
+        save.as_object_mut().unwrap().remove("rng");
+        let loaded = from_str(&save.to_string()).unwrap();
+        assert_eq!(loaded.difficulty, Difficulty::default());
+        assert_eq!(loaded.hot_seat, None);
+        assert!(loaded.rng.is_none());
+    }
+
+    #[test]
+    fn damaged_games_are_refused() {
+        let rules = Rules::default();
+        let mut game = Game::new(
+            rules.clone(),
+            generate_game_board(&rules, &mut rand::rng()),
+            generate_game_board(&rules, &mut rand::rng()),
+        );
+        game.fire(Point(3, 4)).unwrap();
+        game.fire(Point(5, 6)).unwrap();
+        let save = serde_json::to_value(SaveFile::new(game, Difficulty::Hard)).unwrap();
+        assert!(from_str(&save.to_string()).is_ok());
+
+        let damages: [fn(&mut Value); 6] = [
+            |save| {
+                save["game"]["player_board"]["board"]
+                    .as_array_mut()
+                    .unwrap()
+                    .truncate(9)
+            },
+            |save| save["game"]["rules"]["fleet"][0]["len"] = 0.into(),
+            |save| save["game"]["rules"]["width"] = 200.into(),
+            |save| save["game"]["opponent_board"]["ships"][0]["x"] = 99.into(),
+            |save| {
+                let ship = &mut save["game"]["player_board"]["ships"][0];
+                ship["x"] = 255.into();
+                ship["direction"] = "Left".into();
+            },
+            |save| save["game"]["history"][1]["target"] = serde_json::json!([9, 9]),
+        ];
+        for damage in damages {
+            let mut damaged = save.clone();
+            damage(&mut damaged);
+            assert_eq!(
+                from_str(&damaged.to_string()).err(),
+                Some("The save file is corrupted.")
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        assert_eq!(
+            from_str(r#"{"version": 999, "game": {}}"#).err(),
+            Some("The save file is from a newer version of the game.")
+        );
+        assert_eq!(
+            from_str(r#"{"game": {}}"#).err(),
+            Some("The save file has no version.")
+        );
+        assert_eq!(from_str("not json").err(), Some("The save file is corrupted."));
+    }
+}
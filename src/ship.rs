@@ -1,9 +1,98 @@
+use super::rules::Rules;
+use serde::{Deserialize, Serialize};
+
 /// A simple point
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct Point(pub u8, pub u8);
 
+impl Point {
+    /// Parses coordinates such as "B7" or "aa12" into a point
+    pub fn parse(input: &str) -> Result<Self, &'static str> {
+        let mut letters = String::new();
+        let mut digits = String::new();
+        let mut last_char = ' ';
+        for char in input.chars() {
+            if char.is_ascii_alphabetic() {
+                if !letters.is_empty() && !last_char.is_ascii_alphabetic() {
+                    return Err("Multiple Letter Coordinates Provided.");
+                }
+                letters.push(char.to_ascii_uppercase());
+            } else if char.is_ascii_digit() {
+                if !digits.is_empty() && !last_char.is_ascii_digit() {
+                    return Err("Multiple Numeric Coordinates Provided.");
+                }
+                digits.push(char);
+            }
+            last_char = char;
+        }
+        if letters.is_empty() {
+            return Err("No Letter Coordinates Provided.");
+        } else if digits.is_empty() {
+            return Err("No Numeric Coordinates Provided.");
+        }
+
+        // Rows are labelled A to Z, then AA, AB and so on
+        let mut row: u32 = 0;
+        for letter in letters.bytes() {
+            row = row * 26 + (letter - b'A') as u32 + 1;
+            if row > u8::MAX as u32 + 1 {
+                return Err("Out of Bounds.");
+            }
+        }
+        let column: u32 = digits.parse().map_err(|_| "Out of Bounds.")?;
+        if column == 0 || column > u8::MAX as u32 + 1 {
+            return Err("Out of Bounds.");
+        }
+        Ok(Point((column - 1) as u8, (row - 1) as u8))
+    }
+
+    /// Parses several coordinates separated by spaces or commas, such as "B7, C3 d 4"
+    pub fn parse_list(input: &str) -> Result<Vec<Self>, &'static str> {
+        let mut points = vec![];
+        let mut pending = String::new();
+        for part in input.split([',', ' ', '\t']).filter(|part| !part.is_empty()) {
+            pending.push_str(part);
+            // A row and column may be split by a space, so wait until both are present
+            let has_letters = pending.chars().any(|char| char.is_ascii_alphabetic());
+            let has_digits = pending.chars().any(|char| char.is_ascii_digit());
+            if has_letters && has_digits {
+                points.push(Self::parse(&pending)?);
+                pending.clear();
+            }
+        }
+        if !pending.is_empty() {
+            points.push(Self::parse(&pending)?);
+        }
+        Ok(points)
+    }
+
+    /// Check weather the point lies on the board
+    pub fn is_within(&self, rules: &Rules) -> bool {
+        self.0 < rules.width && self.1 < rules.height
+    }
+}
+
+impl std::fmt::Display for Point {
+    /// Formats the point as coordinates such as "B7"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", row_label(self.1), self.0 as u32 + 1)
+    }
+}
+
+/// The letters labelling a row: A to Z, then AA, AB and so on
+pub fn row_label(row: u8) -> String {
+    let mut row = row as u32 + 1;
+    let mut label = vec![];
+    while row > 0 {
+        row -= 1;
+        label.push((b'A' + (row % 26) as u8) as char);
+        row /= 26;
+    }
+    label.iter().rev().collect()
+}
+
 /// The direction the ship is "traveling"
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum ShipDirection {
     Up,
     Down,
@@ -12,39 +101,60 @@ pub enum ShipDirection {
 }
 
 /// The type of ship
-#[derive(Debug, Clone, Copy)]
-pub enum ShipKind {
-    Carrier,
-    Battleship,
-    Cruiser,
-    Submarine,
-    Destroyer,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawShipKind")]
+pub struct ShipKind {
+    name: String,
+    len: u8,
+}
+
+/// A kind of ship as read from a file or the network, before it is checked
+#[derive(Deserialize)]
+struct RawShipKind {
+    name: String,
+    len: u8,
+}
+
+impl TryFrom<RawShipKind> for ShipKind {
+    type Error = &'static str;
+
+    /// Checks a kind of ship that was read like one being created
+    fn try_from(raw: RawShipKind) -> Result<Self, Self::Error> {
+        ShipKind::build(&raw.name, raw.len)
+    }
 }
 
 impl ShipKind {
+    /// Try to create a kind of ship
+    pub fn build(name: &str, len: u8) -> Result<Self, &'static str> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Ship names can't be empty.");
+        }
+        // Names are sent on a line of their own over the network
+        if name.chars().any(char::is_control) {
+            return Err("Ship names can't hold control characters.");
+        }
+        if len == 0 {
+            return Err("Ships must be at least one long.");
+        }
+        Ok(Self {
+            name: name.to_string(),
+            len,
+        })
+    }
     /// Length of the ship
     pub fn get_len(&self) -> u8 {
-        match self {
-            ShipKind::Carrier => 5,
-            ShipKind::Battleship => 4,
-            ShipKind::Cruiser => 3,
-            ShipKind::Submarine => 3,
-            ShipKind::Destroyer => 2,
-        }
+        self.len
     }
     /// Name of the ship
-    pub fn get_name(&self) -> &'static str {
-        match self {
-            ShipKind::Carrier => "Carrier",
-            ShipKind::Battleship => "Battleship",
-            ShipKind::Cruiser => "Cruiser",
-            ShipKind::Submarine => "Submarine",
-            ShipKind::Destroyer => "Destroyer",
-        }
+    pub fn get_name(&self) -> &str {
+        &self.name
     }
 }
+
 /// Stores ships
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ship {
     pub x: u8,
     pub y: u8,
@@ -62,24 +172,67 @@ impl Ship {
         x: u8,
         y: u8,
         direction: ShipDirection,
+        rules: &Rules,
     ) -> Result<Self, &'static str> {
-        if Self::can_exist(&kind, x, y, &direction) {
+        if Self::can_exist(&kind, x, y, &direction, rules) {
             let mut ship = Self {     
                 x, y, direction,           
                 kind,
                 points: vec![],
                 hit_points: vec![]
             };
-            ship.reset_points();
+            ship.reset_points(rules);
             Ok(ship)
         } else {
             Err("Out of bounds.")
         }
     }
 
+    /// A ship waiting to be placed in the top left corner, lying down the board if it fits there
+    pub fn start(kind: ShipKind, rules: &Rules) -> Self {
+        Self::build(kind.clone(), 0, 0, ShipDirection::Up, rules)
+            .or_else(|_| Self::build(kind, 0, 0, ShipDirection::Left, rules))
+            .expect("Rules only allow ships that fit down or across the board")
+    }
+
+    /// Parses a placement such as "A1 down", giving the head of the ship and the way the rest
+    /// of it lies, or "C5-C9", giving both ends
+    pub fn parse(kind: ShipKind, input: &str, rules: &Rules) -> Result<Self, &'static str> {
+        let (x, y, direction) = if let Some((start, end)) = input.split_once('-') {
+            let (start, end) = (Point::parse(start)?, Point::parse(end)?);
+            let (direction, length) = if start.1 == end.1 {
+                (ShipDirection::Left, start.0.abs_diff(end.0))
+            } else if start.0 == end.0 {
+                (ShipDirection::Up, start.1.abs_diff(end.1))
+            } else {
+                return Err("Ships must lie in a straight line.");
+            };
+            if length as u16 + 1 != kind.get_len() as u16 {
+                return Err("The ends don't match the length of the ship.");
+            }
+            (start.0.min(end.0), start.1.min(end.1), direction)
+        } else {
+            let (head, way) = input
+                .trim()
+                .rsplit_once(char::is_whitespace)
+                .ok_or("Give a direction too, such as \"A1 down\".")?;
+            let head = Point::parse(head)?;
+            // Ships travel head first, so one lying down from its head is travelling up
+            let direction = match way.to_ascii_lowercase().as_str() {
+                "down" => ShipDirection::Up,
+                "up" => ShipDirection::Down,
+                "right" => ShipDirection::Left,
+                "left" => ShipDirection::Right,
+                _ => return Err("Directions are up, down, left or right."),
+            };
+            (head.0, head.1, direction)
+        };
+        Self::build(kind, x, y, direction, rules).map_err(|_| "The ship goes off the board there.")
+    }
+
     /// Resets the points based on x, y, and direction
-    pub fn reset_points(&mut self) {
-        if Self::can_exist(&self.kind, self.x, self.y, &self.direction) {
+    pub fn reset_points(&mut self, rules: &Rules) {
+        if Self::can_exist(&self.kind, self.x, self.y, &self.direction, rules) {
             let length = self.kind.get_len() - 1; // Do not count the head
         let (x_min, x_max, y_min, y_max) = match &self.direction {
             ShipDirection::Down => (self.x, self.x, self.y - length, self.y),
@@ -94,22 +247,26 @@ impl Ship {
     }
 
     /// Check if a ship can be created from the parameters
-    pub fn can_exist(kind: &ShipKind, x: u8, y: u8, direction: &ShipDirection) -> bool {
+    pub fn can_exist(
+        kind: &ShipKind,
+        x: u8,
+        y: u8,
+        direction: &ShipDirection,
+        rules: &Rules,
+    ) -> bool {
         let length = kind.get_len() - 1; // Do not count the head
 
-        let tail = match direction {
+        let (tail_x, tail_y) = match direction {
             ShipDirection::Down if y < length => return false,
-            ShipDirection::Down => y - length,
-            ShipDirection::Up => y + length,
-            ShipDirection::Left => x + length,
+            ShipDirection::Down => (x, y - length),
+            ShipDirection::Up if y > u8::MAX - length => return false,
+            ShipDirection::Up => (x, y + length),
+            ShipDirection::Left if x > u8::MAX - length => return false,
+            ShipDirection::Left => (x + length, y),
             ShipDirection::Right if x < length => return false,
-            ShipDirection::Right => x - length,
+            ShipDirection::Right => (x - length, y),
         };
-        let bounds = [x, y, tail];
-        if bounds.iter().any(|pos| *pos > 9) {
-            return false;
-        }
-        true
+        Point(x, y).is_within(rules) && Point(tail_x, tail_y).is_within(rules)
     }
 
     /// Check weather two ships intercept
@@ -119,6 +276,15 @@ impl Ship {
             .any(|point| ship.points.contains(point))
     }
 
+    /// Check weather two ships touch, including diagonally
+    pub fn does_touch(&self, ship: &Ship) -> bool {
+        self.points.iter().any(|point| {
+            ship.points
+                .iter()
+                .any(|other| point.0.abs_diff(other.0) <= 1 && point.1.abs_diff(other.1) <= 1)
+        })
+    }
+
     /// Check weather the ship is hit
     pub fn is_hit_by(&self, point: &Point) -> bool {
         self.points.contains(point)
@@ -137,21 +303,21 @@ impl Ship {
     }
     
     /// Rotates the last ship clockwise if possible
-pub fn rotate(&mut self) {
+pub fn rotate(&mut self, rules: &Rules) {
         let new_direction = match self.direction {
             ShipDirection::Down => ShipDirection::Left,
             ShipDirection::Left => ShipDirection::Up,
             ShipDirection::Up => ShipDirection::Right,
             ShipDirection::Right => ShipDirection::Down,
         };
-        if Ship::can_exist(&self.kind, self.x, self.y, &new_direction) {
+        if Ship::can_exist(&self.kind, self.x, self.y, &new_direction, rules) {
                 self.direction = new_direction;
-                self.reset_points();
+                self.reset_points(rules);
         }
     }
 
 /// Moves the last ship up if possible
-pub fn move_up(&mut self) {
+pub fn move_up(&mut self, rules: &Rules) {
         if self.y == 0 {
             return;
         }
@@ -160,27 +326,29 @@ pub fn move_up(&mut self) {
             self.x,
             self.y - 1,
             &self.direction,
+            rules,
         ) {
                 self.y -= 1;
-                self.reset_points();
+                self.reset_points(rules);
     }
 }
 
 /// Moves the last ship down if possible
-pub fn move_down(&mut self) {
+pub fn move_down(&mut self, rules: &Rules) {
         if Ship::can_exist(
             &self.kind,
             self.x,
             self.y + 1,
             &self.direction,
+            rules,
         ) {
                 self.y += 1;
-                self.reset_points();
+                self.reset_points(rules);
     }
 }
 
 /// Moves the last ship left if possible
-pub fn move_left(&mut self) {
+pub fn move_left(&mut self, rules: &Rules) {
         if self.x == 0 {
             return;
         }
@@ -189,23 +357,25 @@ pub fn move_left(&mut self) {
             self.x - 1,
             self.y,
             &self.direction,
+            rules,
         ) {
         
                 self.x -= 1;
-                self.reset_points();
+                self.reset_points(rules);
         }
 }
 
 /// Moves the last ship right if possible
-pub fn move_right(&mut self) {
+pub fn move_right(&mut self, rules: &Rules) {
         if Ship::can_exist(
             &self.kind,
             self.x + 1,
             self.y,
             &self.direction,
+            rules,
         ) {
                 self.x += 1;
-                self.reset_points();
+                self.reset_points(rules);
       
     }
 }
@@ -214,27 +384,50 @@ pub fn move_right(&mut self) {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::rules::standard_fleet;
+
+    /// Looks up a kind of ship from the standard fleet
+    fn kind(name: &str) -> ShipKind {
+        standard_fleet()
+            .into_iter()
+            .find(|kind| kind.get_name() == name)
+            .unwrap()
+    }
     #[test]
     fn can_make_ships_at_boundaries() {
+        let rules = Rules::default();
         // Top left corner
-        Ship::build(ShipKind::Destroyer, 1, 1, ShipDirection::Down).unwrap();
-        Ship::build(ShipKind::Destroyer, 1, 1, ShipDirection::Left).unwrap();
-        Ship::build(ShipKind::Destroyer, 0, 0, ShipDirection::Up).unwrap();
-        Ship::build(ShipKind::Destroyer, 0, 0, ShipDirection::Left).unwrap();
+        Ship::build(kind("Destroyer"), 1, 1, ShipDirection::Down, &rules).unwrap();
+        Ship::build(kind("Destroyer"), 1, 1, ShipDirection::Left, &rules).unwrap();
+        Ship::build(kind("Destroyer"), 0, 0, ShipDirection::Up, &rules).unwrap();
+        Ship::build(kind("Destroyer"), 0, 0, ShipDirection::Left, &rules).unwrap();
 
         // Bottom right corner
-        Ship::build(ShipKind::Destroyer, 8, 8, ShipDirection::Up).unwrap();
-        Ship::build(ShipKind::Destroyer, 8, 8, ShipDirection::Right).unwrap();
-        Ship::build(ShipKind::Destroyer, 9, 9, ShipDirection::Down).unwrap();
-        Ship::build(ShipKind::Destroyer, 9, 9, ShipDirection::Right).unwrap();
+        Ship::build(kind("Destroyer"), 8, 8, ShipDirection::Up, &rules).unwrap();
+        Ship::build(kind("Destroyer"), 8, 8, ShipDirection::Right, &rules).unwrap();
+        Ship::build(kind("Destroyer"), 9, 9, ShipDirection::Down, &rules).unwrap();
+        Ship::build(kind("Destroyer"), 9, 9, ShipDirection::Right, &rules).unwrap();
+    }
+
+    #[test]
+    fn long_ships_start_across_short_boards() {
+        let dreadnought = ShipKind::build("Dreadnought", 10).unwrap();
+        let rules = Rules::build(20, 8, vec![dreadnought.clone()]).unwrap();
+        let ship = Ship::start(dreadnought, &rules);
+        assert_eq!(ship.points.first(), Some(&Point(0, 0)));
+        assert_eq!(ship.points.last(), Some(&Point(9, 0)));
+
+        let ship = Ship::start(kind("Destroyer"), &rules);
+        assert_eq!(ship.points, vec![Point(0, 0), Point(0, 1)]);
     }
 
     #[test]
     fn ships_intercepts() {
-        let ship1 = Ship::build(ShipKind::Carrier, 3, 1, ShipDirection::Up).unwrap();
-        let ship2 = Ship::build(ShipKind::Battleship, 2, 3, ShipDirection::Left).unwrap();
-        let ship3 = Ship::build(ShipKind::Destroyer, 2, 4, ShipDirection::Down).unwrap();
-        let ship4 = Ship::build(ShipKind::Submarine, 3, 3, ShipDirection::Right).unwrap();
+        let rules = Rules::default();
+        let ship1 = Ship::build(kind("Carrier"), 3, 1, ShipDirection::Up, &rules).unwrap();
+        let ship2 = Ship::build(kind("Battleship"), 2, 3, ShipDirection::Left, &rules).unwrap();
+        let ship3 = Ship::build(kind("Destroyer"), 2, 4, ShipDirection::Down, &rules).unwrap();
+        let ship4 = Ship::build(kind("Submarine"), 3, 3, ShipDirection::Right, &rules).unwrap();
 
         assert!(ship1.does_intercept(&ship2));
         assert!(ship1.does_intercept(&ship4));
@@ -248,7 +441,8 @@ mod test {
 
     #[test]
     fn ships_get_hit() {
-        let mut ship1 = Ship::build(ShipKind::Carrier, 3, 1, ShipDirection::Up).unwrap();
+        let rules = Rules::default();
+        let mut ship1 = Ship::build(kind("Carrier"), 3, 1, ShipDirection::Up, &rules).unwrap();
         // No hits
         assert!(!ship1.is_hit_by(&Point(3, 0)));
         assert!(!ship1.is_hit_by(&Point(3, 6)));
@@ -257,14 +451,74 @@ mod test {
 
         // Real hits
         assert!(ship1.is_hit_by(&Point(3, 1)));
+        ship1.hit(Point(3, 1));
         assert!(!ship1.is_sunk());
         assert!(ship1.is_hit_by(&Point(3, 2)));
+        ship1.hit(Point(3, 2));
         assert!(!ship1.is_sunk());
         assert!(ship1.is_hit_by(&Point(3, 3)));
+        ship1.hit(Point(3, 3));
         assert!(!ship1.is_sunk());
         assert!(ship1.is_hit_by(&Point(3, 4)));
+        ship1.hit(Point(3, 4));
         assert!(!ship1.is_sunk());
         assert!(ship1.is_hit_by(&Point(3, 5)));
+        ship1.hit(Point(3, 5));
         assert!(ship1.is_sunk());
     }
+
+    #[test]
+    fn points_round_trip_through_coordinates() {
+        assert_eq!(Point::parse("B7"), Ok(Point(6, 1)));
+        assert_eq!(Point::parse(" j 10 "), Ok(Point(9, 9)));
+        assert_eq!(Point::parse("AA1"), Ok(Point(0, 26)));
+        assert_eq!(Point::parse("B7C"), Err("Multiple Letter Coordinates Provided."));
+        assert_eq!(Point::parse("1B7"), Err("Multiple Numeric Coordinates Provided."));
+        assert_eq!(Point::parse("B0"), Err("Out of Bounds."));
+        assert_eq!(Point::parse("7"), Err("No Letter Coordinates Provided."));
+
+        assert_eq!(
+            Point::parse_list("B7, c3 D 4,,e5"),
+            Ok(vec![Point(6, 1), Point(2, 2), Point(3, 3), Point(4, 4)])
+        );
+        assert_eq!(Point::parse_list("B7 C"), Err("No Numeric Coordinates Provided."));
+        assert_eq!(Point::parse_list(""), Ok(vec![]));
+
+        assert_eq!(row_label(25), "Z");
+        assert_eq!(row_label(26), "AA");
+        assert_eq!(row_label(27), "AB");
+        assert_eq!(Point(14, 29).to_string(), "AD15");
+        let point = Point(19, 52);
+        assert_eq!(Point::parse(&point.to_string()), Ok(point));
+    }
+
+    #[test]
+    fn placements_are_parsed() {
+        let rules = Rules::default();
+        let points = |input: &str| Ship::parse(kind("Cruiser"), input, &rules).map(|ship| ship.points);
+        let column = vec![Point(0, 0), Point(0, 1), Point(0, 2)];
+        let row = vec![Point(4, 2), Point(5, 2), Point(6, 2)];
+        assert_eq!(points("A1 down"), Ok(column.clone()));
+        assert_eq!(points("C1 UP"), Ok(column.clone()));
+        assert_eq!(points("A1-C1"), Ok(column));
+        assert_eq!(points("c5 right"), Ok(row.clone()));
+        assert_eq!(points("C7 left"), Ok(row.clone()));
+        assert_eq!(points("C7-C5"), Ok(row));
+
+        assert_eq!(points("A1"), Err("Give a direction too, such as \"A1 down\"."));
+        assert_eq!(points("A1 sideways"), Err("Directions are up, down, left or right."));
+        assert_eq!(points("A1-B2"), Err("Ships must lie in a straight line."));
+        assert_eq!(points("A1-A5"), Err("The ends don't match the length of the ship."));
+        assert_eq!(points("A1 up"), Err("The ship goes off the board there."));
+        assert_eq!(points("J9-J11"), Err("The ship goes off the board there."));
+        // Placements at the very edge of what can be typed don't wrap around
+        assert_eq!(
+            points("IV1 down"),
+            Err("The ship goes off the board there.")
+        );
+        assert_eq!(
+            points("A256 right"),
+            Err("The ship goes off the board there.")
+        );
+    }
 }
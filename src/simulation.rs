@@ -0,0 +1,134 @@
+use rand::Rng;
+
+use super::board::{Placement, generate_layout};
+use super::bot::Targeting;
+use super::engine::{Game, Side};
+use super::rules::Rules;
+
+/// A computer player: how it lays out its fleet and how it fires
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Strategy {
+    pub targeting: Targeting,
+    pub placement: Placement,
+}
+
+impl Strategy {
+    /// Parses a strategy such as "density" or "density/hidden".
+    /// The placement is random unless one is given
+    pub fn parse(strategy: &str) -> Result<Self, &'static str> {
+        let (targeting, placement) = strategy.split_once('/').unwrap_or((strategy, "random"));
+        Ok(Self {
+            targeting: Targeting::parse(targeting)?,
+            placement: Placement::parse(placement)?,
+        })
+    }
+
+    /// Name of the strategy, such as "Density/Hidden"
+    pub fn get_name(&self) -> String {
+        format!(
+            "{}/{}",
+            self.targeting.get_name(),
+            self.placement.get_name()
+        )
+    }
+}
+
+/// How one simulated game ended. Each array holds the first strategy, then the second
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedGame {
+    /// Index of the strategy that won
+    pub winner: usize,
+    /// Shots fired by each strategy
+    pub shots: [u32; 2],
+    /// For each strategy's fleet, in fleet order, weather the ship was still afloat
+    pub afloat: [Vec<bool>; 2],
+}
+
+/// Plays one game between two strategies. `first_to_fire` is the index of the strategy
+/// that shoots first
+pub fn simulate_game(
+    rules: &Rules,
+    strategies: [&Strategy; 2],
+    first_to_fire: usize,
+    rng: &mut impl Rng,
+) -> SimulatedGame {
+    // The first to fire plays as the player, since the player always opens
+    let side_of = |index: usize| {
+        if index == first_to_fire {
+            Side::Player
+        } else {
+            Side::Opponent
+        }
+    };
+    let index_of = |side: Side| if side_of(0) == side { 0 } else { 1 };
+
+    let boards = [0, 1].map(|index| generate_layout(rules, strategies[index].placement, rng));
+    let [first_board, second_board] = boards;
+    let mut game = if first_to_fire == 0 {
+        Game::new(rules.clone(), first_board, second_board)
+    } else {
+        Game::new(rules.clone(), second_board, first_board)
+    };
+
+    let mut shots = [0, 0];
+    while !game.is_game_over() {
+        let shooter = game.turn();
+        let index = index_of(shooter);
+        let view = game.board(shooter.other()).view();
+        let targets = strategies[index]
+            .targeting
+            .gen_volley(&view, game.shots_per_turn(), rng);
+        let outcomes = game
+            .fire_volley(&targets)
+            .expect("Strategies only fire at blank cells");
+        shots[index] += outcomes.len() as u32;
+    }
+
+    let winner = index_of(game.winner().expect("The game is over"));
+    let afloat = [0, 1].map(|index| {
+        game.board(side_of(index))
+            .ships
+            .iter()
+            .map(|ship| !ship.is_sunk())
+            .collect()
+    });
+    SimulatedGame {
+        winner,
+        shots,
+        afloat,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn simulated_games_are_reproducible_and_complete() {
+        let rules = Rules::default();
+        let density = Strategy::parse("density/hidden").unwrap();
+        let random = Strategy::parse("Random").unwrap();
+        assert_eq!(random.placement, Placement::Random);
+        assert_eq!(Strategy::parse("psychic").err(), Some("Unknown targeting."));
+
+        for first_to_fire in [0, 1] {
+            let play = || {
+                simulate_game(
+                    &rules,
+                    [&density, &random],
+                    first_to_fire,
+                    &mut StdRng::seed_from_u64(21),
+                )
+            };
+            let result = play();
+            assert_eq!(result, play());
+
+            // The loser's fleet is gone and the winner fired the last shot
+            let loser = 1 - result.winner;
+            assert!(result.afloat[loser].iter().all(|afloat| !afloat));
+            assert!(result.afloat[result.winner].iter().any(|afloat| *afloat));
+            assert!(result.shots[result.winner] >= 17);
+        }
+    }
+}
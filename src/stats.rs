@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::board::ShotOutcome;
+use super::engine::{Game, Side};
+use serde::{Deserialize, Serialize};
+
+/// What has been recorded about the player's finished games against the Admiral.
+/// Every field has a default, so files written before a field was added still load
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    /// Wins in a row, up to the last game
+    pub streak: u32,
+    pub best_streak: u32,
+    /// Every shot the player fired
+    pub shots: u32,
+    /// The shots that hit a ship
+    pub hits: u32,
+    /// Shots fired in the games that were won
+    pub shots_in_wins: u32,
+    /// The fewest shots a win has taken
+    pub fastest_win: Option<u32>,
+    /// How often each kind of the player's ships was the first of the fleet to be sunk
+    pub sunk_first: BTreeMap<String, u32>,
+    /// Turns on which the player asked for a hint
+    pub hints: u32,
+}
+
+impl Stats {
+    /// Adds a finished game
+    pub fn record(&mut self, game: &Game) {
+        let Some(winner) = game.winner() else {
+            return;
+        };
+        // Counted from the shots fired, since water around sunk ships can be marked too
+        let fired: Vec<_> = game
+            .history()
+            .iter()
+            .filter(|shot| shot.shooter == Side::Player)
+            .collect();
+        let shots = fired.len() as u32;
+        let hits = fired.iter().filter(|shot| shot.outcome.is_hit()).count() as u32;
+
+        self.played += 1;
+        self.shots += shots;
+        self.hits += hits;
+        if winner == Side::Player {
+            self.wins += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+            self.shots_in_wins += shots;
+            self.fastest_win = Some(self.fastest_win.map_or(shots, |fastest| fastest.min(shots)));
+        } else {
+            self.losses += 1;
+            self.streak = 0;
+        }
+        let sunk_first = game.history().iter().find(|shot| {
+            shot.shooter == Side::Opponent
+                && matches!(
+                    shot.outcome,
+                    ShotOutcome::Sunk { .. } | ShotOutcome::FleetDestroyed { .. }
+                )
+        });
+        if let Some(kind) = sunk_first.and_then(|shot| shot.outcome.kind()) {
+            *self
+                .sunk_first
+                .entry(kind.get_name().to_string())
+                .or_default() += 1;
+        }
+    }
+
+    /// The share of shots that hit, from 0 to 1
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.shots > 0).then(|| self.hits as f64 / self.shots as f64)
+    }
+
+    /// The mean number of shots the won games took
+    pub fn average_shots_to_win(&self) -> Option<f64> {
+        (self.wins > 0).then(|| self.shots_in_wins as f64 / self.wins as f64)
+    }
+
+    /// The kinds of ship sunk first, most often first
+    pub fn sunk_first_ranking(&self) -> Vec<(&str, u32)> {
+        let mut ranking: Vec<(&str, u32)> = self
+            .sunk_first
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+            .collect();
+        ranking.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        ranking
+    }
+}
+
+/// Reads the statistics, starting afresh if none have been recorded
+pub fn load_stats(path: &Path) -> Result<Stats, &'static str> {
+    if !path.exists() {
+        return Ok(Stats::default());
+    }
+    let text = fs::read_to_string(path).map_err(|_| "Unable to read the statistics.")?;
+    serde_json::from_str(&text).map_err(|_| "The statistics file is corrupted.")
+}
+
+/// Writes the statistics to disk
+pub fn save_stats(path: &Path, stats: &Stats) -> Result<(), &'static str> {
+    let text =
+        serde_json::to_string_pretty(stats).map_err(|_| "Unable to serialise the statistics.")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|_| "Unable to create the save folder.")?;
+    }
+    fs::write(path, text).map_err(|_| "Unable to write the statistics.")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::GameBoard;
+    use crate::rules::Rules;
+    use crate::ship::{Point, Ship, ShipDirection, ShipKind};
+
+    /// A game where each side has a single destroyer in the top left corner
+    fn duel(rules: &Rules) -> Game {
+        let kind = ShipKind::build("Destroyer", 2).unwrap();
+        let board = || {
+            let ship = Ship::build(kind.clone(), 0, 0, ShipDirection::Up, rules).unwrap();
+            GameBoard::build(vec![ship], rules)
+        };
+        Game::new(rules.clone(), board(), board())
+    }
+
+    #[test]
+    fn games_are_recorded() {
+        let rules = Rules::default();
+        let mut stats = Stats::default();
+        assert_eq!(stats.accuracy(), None);
+
+        // A win in three shots, one of them a miss
+        let mut won = duel(&rules);
+        for (i, target) in [Point(0, 0), Point(5, 5), Point(0, 1)]
+            .into_iter()
+            .enumerate()
+        {
+            won.fire(target).unwrap();
+            if !won.is_game_over() {
+                won.fire(Point(9, i as u8)).unwrap();
+            }
+        }
+        stats.record(&won);
+        assert_eq!((stats.played, stats.wins, stats.streak), (1, 1, 1));
+        assert_eq!(stats.fastest_win, Some(3));
+        assert_eq!(stats.accuracy(), Some(2.0 / 3.0));
+
+        // A loss in which the destroyer was sunk first
+        let mut lost = duel(&rules);
+        for target in [Point(0, 0), Point(0, 1)] {
+            lost.fire(Point(9, target.1)).unwrap();
+            lost.fire(target).unwrap();
+        }
+        stats.record(&lost);
+        assert_eq!((stats.played, stats.losses, stats.streak), (2, 1, 0));
+        assert_eq!(stats.best_streak, 1);
+        assert_eq!(stats.average_shots_to_win(), Some(3.0));
+        assert_eq!(stats.sunk_first_ranking(), vec![("Destroyer", 1)]);
+
+        // Older files without some fields still load
+        let loaded: Stats = serde_json::from_str(r#"{"played": 2, "wins": 1}"#).unwrap();
+        assert_eq!(
+            (loaded.played, loaded.wins, loaded.fastest_win, loaded.hints),
+            (2, 1, None, 0)
+        );
+    }
+}
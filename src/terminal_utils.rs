@@ -1,3 +1,4 @@
+use cl_battleship::ship::row_label;
 use console::{Alignment, Style, Term, style};
 
 /// Center aligns text and prints it in the terminal
@@ -28,26 +29,40 @@ pub fn join(first: String, second: String, space: usize) -> String {
         .collect()
 }
 
+/// Width in characters of a grid created by `create_colored_grid`
+pub fn grid_width(columns: u8, rows: u8) -> usize {
+    row_label_width(rows) + 4 * columns as usize + 1
+}
+
+/// Width of the row label column, including its padding
+pub fn row_label_width(rows: u8) -> usize {
+    row_label(rows.saturating_sub(1)).len() + 2
+}
+
 /// Creates a coloured Battle Ship grid
 pub fn create_colored_grid(colours: &[Vec<Style>]) -> String {
-    let mut grid = style(" ".repeat(44))
+    let rows = colours.len() as u8;
+    let columns = colours.first().map_or(0, |row| row.len()) as u8;
+    let label_width = row_label_width(rows);
+    let header: String = (1..=columns as u32).map(|n| format!(" {:<3}", n)).collect();
+    let mut grid = style(" ".repeat(grid_width(columns, rows)))
         //.on_black()
         .to_string()
         + "\n"
-        + &style("    1   2   3   4   5   6   7   8   9   10  ")
+        + &style(format!("{}{} ", " ".repeat(label_width), header))
             .bold()
             // .on_black()
             .to_string()
         + "\n";
     colours.iter().enumerate().for_each(|(i, row)| {
         (0..2).for_each(|k| {
-            let first_char = if k == 0 {
-                std::char::from_u32('A' as u32 + i as u32).unwrap_or('a')
+            let label = if k == 0 {
+                row_label(i as u8)
             } else {
-                ' '
+                String::new()
             };
 
-            let mut line = style(format!(" {} ", first_char))
+            let mut line = style(format!(" {:<1$} ", label, label_width - 2))
                 .bold()
                 // .on_black()
                 .to_string();
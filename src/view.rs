@@ -0,0 +1,170 @@
+use super::board::{GridState, ShotOutcome, mark_around};
+use super::ship::{Point, ShipKind};
+
+/// A ship that was announced as sunk, and the shot that sank it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SunkReport {
+    pub kind: ShipKind,
+    pub at: Point,
+}
+
+/// What the side firing at a board legitimately knows about it: the board's size and
+/// fleet, where it has hit and missed, and which ships were announced sunk.
+/// Ship positions are never part of a view
+#[derive(Debug, Clone)]
+pub struct BoardView {
+    pub width: u8,
+    pub height: u8,
+    /// Indexed by row, then column
+    pub board: Vec<Vec<GridState>>,
+    /// Every ship of the fleet, sunk or not
+    pub fleet: Vec<ShipKind>,
+    pub sunk: Vec<SunkReport>,
+}
+
+impl BoardView {
+    /// Create the view of a board no shots have been fired at
+    pub fn new(width: u8, height: u8, fleet: Vec<ShipKind>) -> Self {
+        Self {
+            width,
+            height,
+            board: vec![vec![GridState::Blank; width as usize]; height as usize],
+            fleet,
+            sunk: vec![],
+        }
+    }
+
+    /// Records what a shot revealed
+    pub fn record(&mut self, target: &Point, outcome: &ShotOutcome) {
+        self.board[target.1 as usize][target.0 as usize] = if outcome.is_hit() {
+            GridState::Hit
+        } else {
+            GridState::Miss
+        };
+        if let ShotOutcome::Sunk { kind } | ShotOutcome::FleetDestroyed { kind } = outcome {
+            self.sunk.push(SunkReport {
+                kind: kind.clone(),
+                at: target.clone(),
+            });
+        }
+    }
+
+    /// Marks the water around every ship reported sunk as missed, for games where ships may
+    /// not touch
+    pub fn mark_around_sunk(&mut self) {
+        let sunk_points = self.sunk_points();
+        mark_around(&mut self.board, &sunk_points);
+    }
+
+    /// What is known about a cell
+    pub fn get(&self, point: &Point) -> GridState {
+        self.board[point.1 as usize][point.0 as usize]
+    }
+
+    /// The ships that have not been announced sunk
+    pub fn remaining(&self) -> Vec<ShipKind> {
+        let mut remaining = self.fleet.clone();
+        for report in self.sunk.iter() {
+            if let Some(index) = remaining.iter().position(|kind| *kind == report.kind) {
+                remaining.remove(index);
+            }
+        }
+        remaining
+    }
+
+    /// Deduces which hits belong to sunk ships. Each sunk ship must lie in a straight line
+    /// of hits through the shot that sank it; lines that can only be placed one way are
+    /// resolved first, then any ambiguity is settled by taking the first line that fits
+    pub fn sunk_points(&self) -> Vec<Point> {
+        let mut claimed: Vec<Point> = vec![];
+        let mut pending: Vec<&SunkReport> = self.sunk.iter().collect();
+        while !pending.is_empty() {
+            let candidates: Vec<Vec<Vec<Point>>> = pending
+                .iter()
+                .map(|report| self.sunk_lines(report, &claimed))
+                .collect();
+            let index = candidates
+                .iter()
+                .position(|lines| lines.len() == 1)
+                .unwrap_or(0);
+            let report = pending.remove(index);
+            match candidates[index].first() {
+                Some(line) => claimed.extend(line.iter().cloned()),
+                // The reports don't add up, so only the sinking shot is certain
+                None => claimed.push(report.at.clone()),
+            }
+        }
+        claimed
+    }
+
+    /// The straight lines of unclaimed hits that the reported ship could occupy
+    fn sunk_lines(&self, report: &SunkReport, claimed: &[Point]) -> Vec<Vec<Point>> {
+        let len = report.kind.get_len() as i32;
+        let (at_x, at_y) = (report.at.0 as i32, report.at.1 as i32);
+        let mut lines = vec![];
+        for (dx, dy) in [(1, 0), (0, 1)] {
+            for offset in 0..len {
+                let (start_x, start_y) = (at_x - dx * offset, at_y - dy * offset);
+                let line: Option<Vec<Point>> = (0..len)
+                    .map(|i| {
+                        let (x, y) = (start_x + dx * i, start_y + dy * i);
+                        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+                            return None;
+                        }
+                        let point = Point(x as u8, y as u8);
+                        (self.get(&point) == GridState::Hit && !claimed.contains(&point))
+                            .then_some(point)
+                    })
+                    .collect();
+                if let Some(line) = line {
+                    // A length one ship is found twice, once in each direction
+                    if !lines.contains(&line) {
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::standard_fleet;
+
+    #[test]
+    fn sunk_ships_are_deduced_from_hits() {
+        let fleet = standard_fleet();
+        let destroyer = fleet[4].clone();
+        let mut view = BoardView::new(10, 10, fleet.clone());
+        view.record(&Point(2, 2), &ShotOutcome::Hit { kind: destroyer.clone() });
+        view.record(&Point(3, 2), &ShotOutcome::Sunk { kind: destroyer.clone() });
+        // A hit on another ship next to the destroyer
+        view.record(&Point(3, 3), &ShotOutcome::Hit { kind: fleet[0].clone() });
+        view.record(&Point(5, 5), &ShotOutcome::Miss);
+
+        assert_eq!(view.get(&Point(5, 5)), GridState::Miss);
+        assert_eq!(view.remaining().len(), 4);
+        assert!(!view.remaining().contains(&destroyer));
+        // The destroyer could lie across or down, so the first line that fits is taken
+        let sunk = view.sunk_points();
+        assert_eq!(sunk.len(), 2);
+        assert!(sunk.contains(&Point(2, 2)));
+        assert!(sunk.contains(&Point(3, 2)));
+
+        // Once the carrier is sunk down the column, only one line is left for each ship
+        for y in 4..8 {
+            let outcome = if y == 7 {
+                ShotOutcome::Sunk { kind: fleet[0].clone() }
+            } else {
+                ShotOutcome::Hit { kind: fleet[0].clone() }
+            };
+            view.record(&Point(3, y), &outcome);
+        }
+        let sunk = view.sunk_points();
+        assert_eq!(sunk.len(), 7);
+        assert!((3..8).all(|y| sunk.contains(&Point(3, y))));
+        assert!(sunk.contains(&Point(2, 2)));
+    }
+}